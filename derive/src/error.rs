@@ -1,5 +1,8 @@
 use std;
 
+use proc_macro2::{Span, TokenStream};
+use syn;
+
 use json::JsonError;
 
 /// The result type for this procedural macro.
@@ -7,10 +10,19 @@ pub type Result<T> = std::result::Result<T, Error>;
 
 /// Represents errors that may be encountered in
 /// invokations of this procedural macro.
+///
+/// # Note
+///
+/// An error may carry further errors found in the same invokation,
+/// so that all of them are reported at once.
 #[derive(Debug)]
 pub struct Error {
 	/// The kind of this error.
 	kind: ErrorKind,
+	/// The location of the offending code.
+	span: Span,
+	/// The errors found in addition to this one.
+	others: Vec<Error>,
 }
 
 /// Kinds of errors that may be encountered in invokations
@@ -29,22 +41,126 @@ pub enum ErrorKind {
 		/// The index of the malformatted argument.
 		index: usize,
 	},
+	/// When `eth_abi` is applied to something other than a trait.
+	NotATrait,
+	/// When the constructor is declared as an event.
+	ConstructorEvent,
+	/// When the constructor is declared as constant.
+	ConstantConstructor,
+	/// When a method is declared as constant and payable.
+	ConstantAndPayable {
+		/// The name of the method.
+		method: String,
+	},
+	/// When the error type of a method returning `Result<T, E>`
+	/// is neither a string nor a struct deriving `AbiType`.
+	InvalidErrorType {
+		/// The name of the method.
+		method: String,
+	},
+	/// When the `event` attribute has an unsupported option.
+	InvalidEventOption {
+		/// The name of the event.
+		event: String,
+	},
+	/// When a parameter of an event is not an identifier.
+	InvalidEventParameter {
+		/// The name of the event.
+		event: String,
+	},
+	/// When an event has more indexed parameters than there are topics.
+	TooManyIndexed {
+		/// The name of the event.
+		event: String,
+		/// The number of indexed parameters.
+		found: usize,
+		/// The maximum number of indexed parameters.
+		max: usize,
+	},
+	/// When the `abi` attribute has an unsupported or malformed option.
+	InvalidAbiOption,
+	/// When an option of the `abi` attribute is given for an item it doesn't apply to.
+	MisplacedAbiOption {
+		/// The name of the option.
+		option: &'static str,
+		/// The kind of item the option is given for.
+		item: &'static str,
+	},
+	/// When a parameter attribute is given for a method it doesn't apply to.
+	MisplacedParamAttribute {
+		/// The name of the attribute.
+		attribute: String,
+		/// The name of the parameter.
+		param: String,
+		/// The name of the method.
+		method: String,
+	},
+	/// When two methods have the same function selector.
+	SelectorCollision {
+		/// The colliding function selector.
+		selector: u32,
+		/// The name of the colliding method.
+		method: String,
+		/// The canonical signature of the colliding method.
+		canonical: String,
+		/// The description of the method declared before.
+		other: String,
+	},
+	/// When the selector given for a method differs from the one of its signature.
+	SelectorMismatch {
+		/// The given function selector.
+		selector: u32,
+		/// The name of the method.
+		method: String,
+		/// The canonical signature of the method.
+		canonical: String,
+		/// The function selector of the canonical signature.
+		expected: u32,
+	},
+	/// When a type is neither supported by the abi nor a struct deriving `AbiType`.
+	UnknownType {
+		/// The name of the type.
+		name: String,
+	},
+	/// When a type can't be represented in the abi.
+	UnsupportedType,
+	/// When a reference is neither `&[u8]` nor `&str`.
+	UnsupportedBorrow,
+	/// When the length of an array type is not an integer literal.
+	InvalidArrayLength,
+	/// When `AbiType` is derived for a generic struct.
+	GenericStruct {
+		/// The name of the struct.
+		name: String,
+	},
+	/// When `AbiType` is derived for something other than a struct with named fields.
+	UnnamedFields,
+	/// When `AbiType` is derived for a struct without fields.
+	EmptyStruct {
+		/// The name of the struct.
+		name: String,
+	},
+	/// When `AbiType` is derived for structs with the same name but other fields.
+	ConflictingStruct {
+		/// The name of the struct.
+		name: String,
+	},
 }
 
 impl From<JsonError> for Error {
 	fn from(json_err: JsonError) -> Self {
-		Error::from_kind(ErrorKind::JsonError(json_err))
+		Error::from_kind(Span::call_site(), ErrorKind::JsonError(json_err))
 	}
 }
 
 impl Error {
-	/// Create an error from the given kind.
+	/// Create an error at the given span from the given kind.
 	///
 	/// # Note
 	///
 	/// Just a private convenience constructor.
-	fn from_kind(kind: ErrorKind) -> Self {
-		Error { kind }
+	fn from_kind(span: Span, kind: ErrorKind) -> Self {
+		Error { kind, span, others: Vec::new() }
 	}
 
 	/// Returns the error kind of `self`.
@@ -55,15 +171,187 @@ impl Error {
 	/// Returns an error representing that an invalid number of
 	/// arguments passed to `eth_abi` have been found.
 	pub fn invalid_number_of_arguments(found: usize) -> Self {
-		assert!(found != 1 && found != 2);
-		Error::from_kind(ErrorKind::InvalidNumberOfArguments { found })
+		assert!(found == 0 || found > 2);
+		Error::from_kind(Span::call_site(), ErrorKind::InvalidNumberOfArguments { found })
 	}
 
 	/// Returns an error representing a malformatted argument passed to
 	/// `eth_abi` has been found at the given index.
-	pub fn malformatted_argument(index: usize) -> Self {
-		assert!(index <= 1);
-		Error::from_kind(ErrorKind::MalformattedArgument { index })
+	pub fn malformatted_argument(span: Span, index: usize) -> Self {
+		assert!(index <= 2);
+		Error::from_kind(span, ErrorKind::MalformattedArgument { index })
+	}
+
+	/// Returns an error representing that `eth_abi` is applied to something other than a trait.
+	pub fn not_a_trait(span: Span) -> Self {
+		Error::from_kind(span, ErrorKind::NotATrait)
+	}
+
+	/// Returns an error representing that the constructor is declared as an event.
+	pub fn constructor_event(span: Span) -> Self {
+		Error::from_kind(span, ErrorKind::ConstructorEvent)
+	}
+
+	/// Returns an error representing that the constructor is declared as constant.
+	pub fn constant_constructor(span: Span) -> Self {
+		Error::from_kind(span, ErrorKind::ConstantConstructor)
+	}
+
+	/// Returns an error representing that the given method is declared as constant and payable.
+	pub fn constant_and_payable(span: Span, method: &syn::Ident) -> Self {
+		Error::from_kind(span, ErrorKind::ConstantAndPayable { method: method.to_string() })
+	}
+
+	/// Returns an error representing that the error type of the given method is not supported.
+	pub fn invalid_error_type(span: Span, method: &syn::Ident) -> Self {
+		Error::from_kind(span, ErrorKind::InvalidErrorType { method: method.to_string() })
+	}
+
+	/// Returns an error representing that the `event` attribute of the given event
+	/// has an unsupported option.
+	pub fn invalid_event_option(span: Span, event: &syn::Ident) -> Self {
+		Error::from_kind(span, ErrorKind::InvalidEventOption { event: event.to_string() })
+	}
+
+	/// Returns an error representing that a parameter of the given event is not an identifier.
+	pub fn invalid_event_parameter(span: Span, event: &syn::Ident) -> Self {
+		Error::from_kind(span, ErrorKind::InvalidEventParameter { event: event.to_string() })
+	}
+
+	/// Returns an error representing that the given event has too many indexed parameters.
+	pub fn too_many_indexed(span: Span, event: &syn::Ident, found: usize, max: usize) -> Self {
+		Error::from_kind(span, ErrorKind::TooManyIndexed { event: event.to_string(), found, max })
+	}
+
+	/// Returns an error representing that the `abi` attribute has an unsupported or malformed option.
+	pub fn invalid_abi_option(span: Span) -> Self {
+		Error::from_kind(span, ErrorKind::InvalidAbiOption)
+	}
+
+	/// Returns an error representing that the given option of the `abi` attribute
+	/// doesn't apply to the given kind of item.
+	pub fn misplaced_abi_option(span: Span, option: &'static str, item: &'static str) -> Self {
+		Error::from_kind(span, ErrorKind::MisplacedAbiOption { option, item })
+	}
+
+	/// Returns an error representing that the given attribute of a parameter
+	/// doesn't apply to the method, which is no event.
+	pub fn misplaced_param_attribute(span: Span, attribute: &str, param: &str, method: &str) -> Self {
+		Error::from_kind(span, ErrorKind::MisplacedParamAttribute {
+			attribute: attribute.to_owned(),
+			param: param.to_owned(),
+			method: method.to_owned(),
+		})
+	}
+
+	/// Returns an error representing that the given method with the canonical
+	/// signature `canonical` has the same selector as `other`.
+	pub fn selector_collision(span: Span, selector: u32, method: &syn::Ident, canonical: &str, other: &str) -> Self {
+		Error::from_kind(span, ErrorKind::SelectorCollision {
+			selector,
+			method: method.to_string(),
+			canonical: canonical.to_owned(),
+			other: other.to_owned(),
+		})
+	}
+
+	/// Returns an error representing that the selector given for a method
+	/// differs from the one of its canonical signature.
+	pub fn selector_mismatch(span: Span, selector: u32, method: &syn::Ident, canonical: &str, expected: u32) -> Self {
+		Error::from_kind(span, ErrorKind::SelectorMismatch {
+			selector,
+			method: method.to_string(),
+			canonical: canonical.to_owned(),
+			expected,
+		})
+	}
+
+	/// Returns an error representing that the type with the given name is unknown to the abi.
+	pub fn unknown_type(span: Span, name: &str) -> Self {
+		Error::from_kind(span, ErrorKind::UnknownType { name: name.to_owned() })
+	}
+
+	/// Returns an error representing that a type can't be represented in the abi.
+	pub fn unsupported_type(span: Span) -> Self {
+		Error::from_kind(span, ErrorKind::UnsupportedType)
+	}
+
+	/// Returns an error representing that a reference is neither `&[u8]` nor `&str`.
+	pub fn unsupported_borrow(span: Span) -> Self {
+		Error::from_kind(span, ErrorKind::UnsupportedBorrow)
+	}
+
+	/// Returns an error representing that the length of an array type is not an integer literal.
+	pub fn invalid_array_length(span: Span) -> Self {
+		Error::from_kind(span, ErrorKind::InvalidArrayLength)
+	}
+
+	/// Returns an error representing that `AbiType` is derived for the given generic struct.
+	pub fn generic_struct(span: Span, name: &syn::Ident) -> Self {
+		Error::from_kind(span, ErrorKind::GenericStruct { name: name.to_string() })
+	}
+
+	/// Returns an error representing that `AbiType` is derived for something
+	/// other than a struct with named fields.
+	pub fn unnamed_fields(span: Span) -> Self {
+		Error::from_kind(span, ErrorKind::UnnamedFields)
+	}
+
+	/// Returns an error representing that `AbiType` is derived for the given struct without fields.
+	pub fn empty_struct(span: Span, name: &syn::Ident) -> Self {
+		Error::from_kind(span, ErrorKind::EmptyStruct { name: name.to_string() })
+	}
+
+	/// Returns an error representing that `AbiType` is derived for another struct
+	/// with the given name but other fields.
+	pub fn conflicting_struct(span: Span, name: &syn::Ident) -> Self {
+		Error::from_kind(span, ErrorKind::ConflictingStruct { name: name.to_string() })
+	}
+
+	/// Adds `other` and the errors it carries to the errors reported along with `self`.
+	pub fn combine(&mut self, mut other: Error) {
+		let others: Vec<Error> = other.others.drain(..).collect();
+		self.others.push(other);
+		self.others.extend(others);
+	}
+
+	/// Returns a `compile_error!` invokation for `self` and every error
+	/// carried by it, each located at the offending code.
+	pub fn to_compile_error(&self) -> TokenStream {
+		let mut tokens = syn::Error::new(self.span, self).to_compile_error();
+		for other in &self.others {
+			tokens.extend(other.to_compile_error());
+		}
+		tokens
+	}
+}
+
+/// Collects the values of all `results`, or combines all their errors into one.
+pub fn collect<T, I: IntoIterator<Item = Result<T>>>(results: I) -> Result<Vec<T>> {
+	let mut values = Vec::new();
+	let mut error: Option<Error> = None;
+	for result in results {
+		match (result, error.as_mut()) {
+			(Ok(value), _) => values.push(value),
+			(Err(err), Some(error)) => error.combine(err),
+			(Err(err), None) => error = Some(err),
+		}
+	}
+	match error {
+		Some(error) => Err(error),
+		None => Ok(values),
+	}
+}
+
+/// Joins the values of `first` and `second`, or combines their errors into one.
+pub fn join<T, U>(first: Result<T>, second: Result<U>) -> Result<(T, U)> {
+	match (first, second) {
+		(Ok(first), Ok(second)) => Ok((first, second)),
+		(Err(mut first), Err(second)) => {
+			first.combine(second);
+			Err(first)
+		},
+		(Err(error), _) | (_, Err(error)) => Err(error),
 	}
 }
 
@@ -73,7 +361,7 @@ impl std::fmt::Display for Error {
 			ErrorKind::JsonError(err) => write!(f, "{}", err),
 			ErrorKind::InvalidNumberOfArguments { found } => write!(
 				f,
-				"found {} arguments passed to eth_abi but expected 1 or 2",
+				"found {} arguments passed to eth_abi but expected 1 or 2 names followed by an optional `strict`",
 				found
 			),
 			ErrorKind::MalformattedArgument { index } => write!(
@@ -81,6 +369,95 @@ impl std::fmt::Display for Error {
 				"found non-identifier argument at index {} passed to eth_abi",
 				index
 			),
+			ErrorKind::NotATrait => write!(f, "eth_abi can only be applied to trait declarations"),
+			ErrorKind::ConstructorEvent => write!(f, "the constructor can't be an event"),
+			ErrorKind::ConstantConstructor => write!(f, "the constructor can't be constant"),
+			ErrorKind::ConstantAndPayable { method } => write!(
+				f,
+				"method {} can't be constant and payable at the same time",
+				method
+			),
+			ErrorKind::InvalidErrorType { method } => write!(
+				f,
+				"the error type of {} has to be a String or a struct deriving AbiType",
+				method
+			),
+			ErrorKind::InvalidEventOption { event } => write!(
+				f,
+				"event {} has an unsupported option, expected `#[event]` or `#[event(anonymous)]`",
+				event
+			),
+			ErrorKind::InvalidEventParameter { event } => write!(
+				f,
+				"parameters of event {} have to be identifiers",
+				event
+			),
+			ErrorKind::TooManyIndexed { event, found, max } => write!(
+				f,
+				"event {} has {} indexed parameters, but at most {} are allowed",
+				event,
+				found,
+				max
+			),
+			ErrorKind::InvalidAbiOption => write!(
+				f,
+				"unsupported option of the abi attribute, expected `name = \"solidityName\"` or `selector = 0x12345678`"
+			),
+			ErrorKind::MisplacedAbiOption { option, item } => write!(
+				f,
+				"the `{}` option of the abi attribute can't be given for {}",
+				option,
+				item
+			),
+			ErrorKind::MisplacedParamAttribute { attribute, param, method } => write!(
+				f,
+				"attribute #[{}] of parameter {} can't be given for method {}, which is no event",
+				attribute,
+				param,
+				method
+			),
+			ErrorKind::SelectorCollision { selector, method, canonical, other } => write!(
+				f,
+				"selector 0x{:08x} of method {} ({}) collides with {}",
+				selector,
+				method,
+				canonical,
+				other
+			),
+			ErrorKind::SelectorMismatch { selector, method, canonical, expected } => write!(
+				f,
+				"selector 0x{:08x} of method {} differs from 0x{:08x} of its signature {}, \
+				which can be renamed by `name = \"solidityName\"`",
+				selector,
+				method,
+				expected,
+				canonical
+			),
+			ErrorKind::UnknownType { name } => write!(
+				f,
+				"type {} is not supported by the abi (structs have to derive AbiType before being used)",
+				name
+			),
+			ErrorKind::UnsupportedType => write!(f, "type is not supported by the abi"),
+			ErrorKind::UnsupportedBorrow => write!(f, "only &[u8] and &str can be borrowed"),
+			ErrorKind::InvalidArrayLength => write!(f, "the array length has to be an integer literal"),
+			ErrorKind::GenericStruct { name } => write!(
+				f,
+				"AbiType can't be derived for generic struct {}",
+				name
+			),
+			ErrorKind::UnnamedFields => write!(f, "AbiType can be derived for structs with named fields only"),
+			ErrorKind::EmptyStruct { name } => write!(
+				f,
+				"AbiType can't be derived for struct {} without fields",
+				name
+			),
+			ErrorKind::ConflictingStruct { name } => write!(
+				f,
+				"AbiType is already derived for another struct {} with other fields, \
+				structs deriving AbiType have to be named uniquely",
+				name
+			),
 		}
 	}
 }
@@ -90,11 +467,32 @@ impl std::error::Error for Error {
 		match self.kind() {
             ErrorKind::JsonError(err) => err.description(),
 			ErrorKind::InvalidNumberOfArguments{ .. } => {
-				"encountered an invalid number of arguments passed to eth_abi: expected 1 or 2"
+				"encountered an invalid number of arguments passed to eth_abi: expected 1 or 2 names followed by an optional `strict`"
 			},
 			ErrorKind::MalformattedArgument{ .. } => {
 				"encountered malformatted argument passed to eth_abi: expected identifier (e.g. `Foo`))"
-			}
+			},
+			ErrorKind::NotATrait => "eth_abi applied to something other than a trait",
+			ErrorKind::ConstructorEvent => "constructor declared as event",
+			ErrorKind::ConstantConstructor => "constructor declared as constant",
+			ErrorKind::ConstantAndPayable{ .. } => "method declared as constant and payable",
+			ErrorKind::InvalidErrorType{ .. } => "unsupported error type of a method",
+			ErrorKind::InvalidEventOption{ .. } => "unsupported option of an event",
+			ErrorKind::InvalidEventParameter{ .. } => "non-identifier parameter of an event",
+			ErrorKind::TooManyIndexed{ .. } => "too many indexed parameters of an event",
+			ErrorKind::InvalidAbiOption => "unsupported option of the abi attribute",
+			ErrorKind::MisplacedAbiOption{ .. } => "abi option given for an item it doesn't apply to",
+			ErrorKind::MisplacedParamAttribute{ .. } => "parameter attribute given for a method it doesn't apply to",
+			ErrorKind::SelectorCollision{ .. } => "methods with the same selector",
+			ErrorKind::SelectorMismatch{ .. } => "selector differing from the signature of the method",
+			ErrorKind::UnknownType{ .. } => "type unknown to the abi",
+			ErrorKind::UnsupportedType => "type not supported by the abi",
+			ErrorKind::UnsupportedBorrow => "unsupported borrowed type",
+			ErrorKind::InvalidArrayLength => "array length is not an integer literal",
+			ErrorKind::GenericStruct{ .. } => "AbiType derived for generic struct",
+			ErrorKind::UnnamedFields => "AbiType derived for struct without named fields",
+			ErrorKind::EmptyStruct{ .. } => "AbiType derived for struct without fields",
+			ErrorKind::ConflictingStruct{ .. } => "AbiType derived for structs with the same name but other fields",
 		}
 	}
 }
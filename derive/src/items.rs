@@ -1,26 +1,41 @@
-use {quote, syn, utils};
+use {quote, syn, tuple, utils};
+use error::{self, Error, Result};
 
 use quote::TokenStreamExt;
 use proc_macro2::{self, Span};
+use syn::spanned::Spanned;
 
 /// Represents an event of a smart contract.
 pub struct Event {
 	/// The name of the event.
 	pub name: syn::Ident,
+	/// The name of the event in the abi.
+	///
+	/// # Note
+	///
+	/// This is `name` unless given by `#[abi(name = "...")]`.
+	pub abi_name: String,
 	/// The canonalized string representation used by the keccak hash
 	/// in order to retrieve the first 4 bytes required upon calling.
 	pub canonical: String,
-	/// The signature of the event.
+	/// The signature of the method emitting the event,
+	/// which returns the error of encoding the log data.
 	pub method_sig: syn::MethodSig,
 	/// Indexed parameters.
 	/// 
 	/// # Note
 	/// 
-	/// Only up to 4 different parameters can be indexed
-	/// for the same event.
+	/// Only up to 3 different parameters can be indexed
+	/// for the same event, or 4 if it is anonymous.
 	pub indexed: Vec<(syn::Pat, syn::Type)>,
 	/// Non-indexed parameters.
 	pub data: Vec<(syn::Pat, syn::Type)>,
+	/// If this event is anonymous.
+	///
+	/// # Note
+	///
+	/// The signature of an anonymous event is not stored as the first topic.
+	pub anonymous: bool,
 }
 
 /// Represents a function declared in the contracts interface.
@@ -31,16 +46,35 @@ pub struct Event {
 pub struct Signature {
 	/// The name of this signature.
 	pub name: syn::Ident,
+	/// The name of this signature in the abi.
+	///
+	/// # Note
+	///
+	/// This is `name` unless given by `#[abi(name = "...")]`,
+	/// e.g. to bind overloaded Solidity functions to methods of different names.
+	pub abi_name: String,
 	/// The canonicalized string representation of this signature.
 	pub canonical: String,
 	/// The parameter information of this signature.
 	pub method_sig: syn::MethodSig,
 	/// The function selector hash (4 bytes) of this signature.
+	///
+	/// # Note
+	///
+	/// This is derived from `canonical`, which `#[abi(selector = ...)]` is checked to match.
 	pub hash: u32,
 	/// The arguments of this signature.
 	pub arguments: Vec<(syn::Pat, syn::Type)>,
 	/// The return type of this signature.
 	pub return_types: Vec<syn::Type>,
+	/// The type of the value returned on success.
+	///
+	/// # Note
+	///
+	/// This is `T` for signatures returning `Result<T, E>`.
+	pub output: Option<syn::Type>,
+	/// How this signature reverts if it returns `Result<T, E>`.
+	pub revert: Option<Revert>,
 	/// If this signature is constant.
 	/// 
 	/// # Note
@@ -55,6 +89,30 @@ pub struct Signature {
 	pub is_payable: bool,
 }
 
+/// The way a signature returning `Result<T, E>` reverts on `Err`.
+#[derive(Clone)]
+pub enum Revert {
+	/// `E` is a string that is reported as Solidity `Error(string)` reason.
+	Reason,
+	/// `E` is a struct deriving `AbiType` that is reported as Solidity custom error.
+	Custom(CustomError),
+}
+
+/// A Solidity custom error, e.g. `error InsufficientBalance(uint256,uint256)`.
+#[derive(Clone)]
+pub struct CustomError {
+	/// The name of the error.
+	pub name: String,
+	/// The canonicalized string representation of the error.
+	pub canonical: String,
+	/// The error selector hash (4 bytes).
+	pub selector: u32,
+	/// The type of the error.
+	pub ty: syn::Type,
+	/// The fields of the error.
+	pub components: Vec<tuple::Component>,
+}
+
 /// An item within a contract trait.
 pub enum Item {
 	/// An invokable function.
@@ -104,31 +162,51 @@ impl Item {
 }
 
 impl Interface {
-	pub fn from_item(source: syn::Item) -> Self {
+	/// Creates the interface of the given trait.
+	///
+	/// Fails with the errors of all invalid items of the trait.
+	pub fn from_item(source: syn::Item, param_attributes: &utils::ParamAttributes) -> Result<Self> {
 		let item_trait = match source {
 			syn::Item::Trait(item_trait) => item_trait,
-			_ => panic!("Dispatch trait can work with trait declarations only!")
+			other => return Err(Error::not_a_trait(other.span())),
 		};
 		let trait_items = item_trait.items;
+		let events: Vec<syn::Ident> = trait_items.iter()
+			.filter_map(|trait_item| match *trait_item {
+				syn::TraitItem::Method(ref method) if has_attribute(&method.attrs, "event") => {
+					Some(method.sig.ident.clone())
+				},
+				_ => None,
+			})
+			.collect();
 
-		let (constructor_items, other_items) = trait_items
+		let (items, _) = error::join(
+			error::collect(
+				trait_items
+					.into_iter()
+					.map(|trait_item| Item::from_trait_item(trait_item, param_attributes))
+			),
+			param_attributes.check_events(&events),
+		)?;
+		let (constructor_items, other_items) = items
 			.into_iter()
-			.map(Item::from_trait_item)
 			.partition::<Vec<Item>, _>(|item| {
 				item.name().map_or(false, |ident| ident.to_string() == "constructor")
 			});
 
-		Interface {
+		check_selectors(&other_items)?;
+
+		Ok(Interface {
 			constructor: constructor_items
 				.into_iter()
 				.next()
 				.map(|item| match item {
 					Item::Signature(sig) => sig,
-					_ => panic!("The constructor must be function!")
+					_ => unreachable!("the constructor is validated not to be an event"),
 				}),
 			name: item_trait.ident.to_string(),
 			items: other_items,
-		}
+		})
 	}
 
 	pub fn items(&self) -> &[Item] {
@@ -144,103 +222,314 @@ impl Interface {
 	}
 }
 
+/// Signatures of standard methods, which other methods of the
+/// interface must not shadow by having the same selector.
+const STANDARD_SIGNATURES: &[(&str, &str)] = &[
+	("supportsInterface(bytes4)", "ERC-165"),
+];
+
+/// Checks that the selectors of the signatures differ from each other and from
+/// the selectors of the standard methods, unless they declare the standard method.
+///
+/// Fails with an error for every signature colliding with one declared before.
+fn check_selectors(items: &[Item]) -> Result<()> {
+	// The selector, canonical signature and description of every known method,
+	// and whether it is a standard method the interface may declare.
+	let mut known: Vec<(u32, String, String, bool)> = STANDARD_SIGNATURES.iter()
+		.map(|&(canonical, standard)| (
+			utils::function_selector(canonical),
+			canonical.to_owned(),
+			format!("{} of {}", canonical, standard),
+			true,
+		))
+		.collect();
+	let signatures = items.iter().filter_map(|item| match *item {
+		Item::Signature(ref signature) => Some(signature),
+		_ => None,
+	});
+	error::collect(signatures.map(|signature| {
+		let collision = known.iter()
+			.find(|&&(selector, ref canonical, _, is_standard)| {
+				selector == signature.hash && !(is_standard && *canonical == signature.canonical)
+			})
+			.map(|&(_, _, ref other, _)| other.clone());
+		known.push((
+			signature.hash,
+			signature.canonical.clone(),
+			format!("method {} ({})", signature.name, signature.canonical),
+			false,
+		));
+		match collision {
+			Some(other) => Err(Error::selector_collision(
+				signature.name.span(),
+				signature.hash,
+				&signature.name,
+				&signature.canonical,
+				&other,
+			)),
+			None => Ok(()),
+		}
+	}))?;
+	Ok(())
+}
+
+/// Options of the `abi` attribute overriding how a method is represented in the abi.
+#[derive(Default)]
+struct AbiOptions {
+	/// The name used instead of the method name.
+	name: Option<(String, Span)>,
+	/// The function selector used instead of the one derived from the signature.
+	selector: Option<(u32, Span)>,
+}
+
+impl AbiOptions {
+	/// Parses the options of all `abi` attributes, e.g. `#[abi(name = "balanceOf")]`.
+	fn from_attributes(attrs: &[syn::Attribute]) -> Result<Self> {
+		let lists = error::collect(
+			attrs.iter()
+				.filter(|attr| is_attribute(attr, "abi"))
+				.map(|attr| match attr.parse_meta() {
+					Ok(syn::Meta::List(list)) => Ok(list.nested),
+					_ => Err(Error::invalid_abi_option(attr.span())),
+				})
+		)?;
+		let mut options = AbiOptions::default();
+		error::collect(lists.iter().flat_map(|list| list.iter()).map(|nested| {
+			if let syn::NestedMeta::Meta(syn::Meta::NameValue(ref name_value)) = *nested {
+				match (name_value.ident.to_string().as_str(), &name_value.lit) {
+					("name", &syn::Lit::Str(ref lit))
+						if options.name.is_none() && is_solidity_identifier(&lit.value()) =>
+					{
+						options.name = Some((lit.value(), nested.span()));
+						return Ok(());
+					},
+					("selector", &syn::Lit::Int(ref lit))
+						if options.selector.is_none() && lit.value() <= u32::max_value() as u64 =>
+					{
+						options.selector = Some((lit.value() as u32, nested.span()));
+						return Ok(());
+					},
+					_ => {},
+				}
+			}
+			Err(Error::invalid_abi_option(nested.span()))
+		}))?;
+		Ok(options)
+	}
+}
+
+/// Returns `true` if `name` is a valid Solidity identifier.
+fn is_solidity_identifier(name: &str) -> bool {
+	let is_start = |c: char| c.is_ascii_alphabetic() || c == '_' || c == '$';
+	name.chars().next().map_or(false, is_start)
+		&& name.chars().all(|c| is_start(c) || c.is_ascii_digit())
+}
+
 fn into_signature(
 	ident: syn::Ident,
 	method_sig: syn::MethodSig,
 	is_constant: bool,
-	is_payable: bool
+	is_payable: bool,
+	abi_options: AbiOptions,
 )
-	-> Signature
+	-> Result<Signature>
 {
+	let abi_name = abi_options.name.map_or_else(|| ident.to_string(), |(name, _)| name);
 	let arguments: Vec<(syn::Pat, syn::Type)> = utils::iter_signature(&method_sig).collect();
-	let return_types: Vec<syn::Type> = match method_sig.decl.output.clone() {
-		syn::ReturnType::Default => Vec::new(),
-		syn::ReturnType::Type(_, ty) => {
-			match *ty {
-				syn::Type::Tuple(tuple_type) => {
-					tuple_type.elems.into_iter().collect()
-				},
-				ty => vec![ty],
-			}
+	let (output, err_type) = match method_sig.decl.output.clone() {
+		syn::ReturnType::Default => (None, None),
+		syn::ReturnType::Type(_, ty) => match utils::result_types(&ty) {
+			Some((ok_type, err_type)) => (Some(ok_type), Some(err_type)),
+			None => (Some(*ty), None),
 		},
 	};
-	let canonical = utils::canonicalize_fn(&ident, &method_sig);
+	let return_types: Vec<syn::Type> = match output.clone() {
+		None => Vec::new(),
+		Some(syn::Type::Tuple(tuple_type)) => tuple_type.elems.into_iter().collect(),
+		Some(ty) => vec![ty],
+	};
+	// Unsupported parameter, return and error types are reported together.
+	let revert = match err_type {
+		Some(ref err_type) => into_revert(&ident, err_type).map(Some),
+		None => Ok(None),
+	};
+	let return_canonicals = error::collect(return_types.iter().map(utils::canonicalize_type));
+	let ((canonical, _), revert) = error::join(
+		error::join(utils::canonicalize_fn(&abi_name, &method_sig), return_canonicals),
+		revert,
+	)?;
+	// Callers and the json abi derive the selector from the name and types,
+	// so a given selector can only confirm it.
 	let hash = utils::function_selector(&canonical);
+	if let Some((selector, span)) = abi_options.selector {
+		if selector != hash {
+			return Err(Error::selector_mismatch(span, selector, &ident, &canonical, hash));
+		}
+	}
 
-	Signature {
+	Ok(Signature {
 		name: ident,
+		abi_name: abi_name,
 		arguments: arguments,
 		method_sig: method_sig,
 		canonical: canonical,
 		hash: hash,
 		return_types: return_types,
+		output: output,
+		revert: revert,
 		is_constant: is_constant,
 		is_payable: is_payable,
+	})
+}
+
+fn into_revert(ident: &syn::Ident, err_type: &syn::Type) -> Result<Revert> {
+	if utils::is_string_type(err_type) {
+		return Ok(Revert::Reason);
+	}
+	let name = match err_type {
+		syn::Type::Path(type_path) => type_path.path.segments
+			.last()
+			.map(|seg| seg.value().ident.to_string()),
+		_ => None,
+	};
+	let components = name.as_ref().and_then(|name| tuple::lookup(name));
+	match (name, components) {
+		(Some(name), Some(components)) => {
+			let canonical = format!("{}{}", name, utils::canonicalize_type(err_type)?);
+			let selector = utils::function_selector(&canonical);
+			Ok(Revert::Custom(CustomError {
+				name: name,
+				canonical: canonical,
+				selector: selector,
+				ty: err_type.clone(),
+				components: components,
+			}))
+		},
+		_ => Err(Error::invalid_error_type(err_type.span(), ident)),
 	}
 }
 
+fn is_attribute(attr: &syn::Attribute, name: &str) -> bool {
+	if let Some(first_seg) = attr.path.segments.first() {
+		return first_seg.value().ident == name
+	};
+	false
+}
+
+fn find_attribute<'a>(attrs: &'a [syn::Attribute], name: &str) -> Option<&'a syn::Attribute> {
+	attrs.iter().find(|attr| is_attribute(attr, name))
+}
+
 fn has_attribute(attrs: &[syn::Attribute], name: &str) -> bool {
-	attrs.iter().any(|attr| {
-		if let Some(first_seg) = attr.path.segments.first() {
-			return first_seg.value().ident == name
-		};
-		false
-	})
+	find_attribute(attrs, name).is_some()
+}
+
+/// Returns `true` if the event is declared as `#[event(anonymous)]`.
+fn is_anonymous_event(ident: &syn::Ident, attrs: &[syn::Attribute]) -> Result<bool> {
+	let attr = match find_attribute(attrs, "event") {
+		Some(attr) => attr,
+		None => return Ok(false),
+	};
+	match attr.parse_meta() {
+		Ok(syn::Meta::Word(_)) => Ok(false),
+		Ok(syn::Meta::List(ref list)) if list.nested.len() == 1 => match list.nested[0] {
+			syn::NestedMeta::Meta(syn::Meta::Word(ref option)) if option == "anonymous" => Ok(true),
+			ref nested => Err(Error::invalid_event_option(nested.span(), ident)),
+		},
+		_ => Err(Error::invalid_event_option(attr.span(), ident)),
+	}
 }
 
 impl Item {
-	fn event_from_trait_item(method_sig: syn::MethodSig) -> Self {
-		assert!(
-			method_sig.ident != "constructor",
-			"The constructor can't be an event"
-		);
-		let (indexed, non_indexed) = utils::iter_signature(&method_sig)
-			.partition(|&(ref pat, _)| quote! { #pat }.to_string().starts_with("indexed_"));
-		let canonical = utils::canonicalize_fn(&method_sig.ident, &method_sig);
+	fn event_from_trait_item(
+		method_trait_item: syn::TraitItemMethod,
+		param_attributes: &utils::ParamAttributes,
+	)
+		-> Result<Self>
+	{
+		let method_sig = method_trait_item.sig;
+		if method_sig.ident == "constructor" {
+			return Err(Error::constructor_event(method_sig.ident.span()));
+		}
+		let name = &method_sig.ident;
+		let params = error::collect(utils::iter_signature(&method_sig).map(|(pat, ty)| match pat {
+			syn::Pat::Ident(_) => Ok((pat, ty)),
+			_ => Err(Error::invalid_event_parameter(pat.span(), name)),
+		}));
+		let anonymous = is_anonymous_event(name, &method_trait_item.attrs);
+		// Events are identified by the hash of their signature rather than a selector.
+		let abi_name = AbiOptions::from_attributes(&method_trait_item.attrs).and_then(|options| {
+			match options.selector {
+				Some((_, span)) => Err(Error::misplaced_abi_option(span, "selector", "events")),
+				None => Ok(options.name.map_or_else(|| name.to_string(), |(name, _)| name)),
+			}
+		});
+		let ((params, anonymous), abi_name) = error::join(error::join(params, anonymous), abi_name)?;
+		let canonical = utils::canonicalize_fn(&abi_name, &method_sig)?;
+		// Parameters are indexed by the `#[indexed]` attribute or the `indexed_` name prefix.
+		let (indexed, non_indexed): (Vec<_>, Vec<_>) = params
+			.into_iter()
+			.partition(|&(ref pat, _)| {
+				param_attributes.has(name, pat, "indexed")
+					|| quote! { #pat }.to_string().starts_with("indexed_")
+			});
+		let max_indexed = if anonymous { 4 } else { 3 };
+		if indexed.len() > max_indexed {
+			let (ref excess_pat, _) = indexed[max_indexed];
+			return Err(Error::too_many_indexed(excess_pat.span(), name, indexed.len(), max_indexed));
+		}
+		let mut emit_sig = method_sig.clone();
+		emit_sig.decl.output = parse_quote! { -> Result<(), ::pwasm_abi::eth::SinkError> };
 		let event = Event {
 			name: method_sig.ident.clone(),
+			abi_name: abi_name,
 			canonical: canonical,
 			indexed: indexed,
 			data: non_indexed,
-			method_sig: method_sig,
+			anonymous: anonymous,
+			method_sig: emit_sig,
 		};
-		Item::Event(event)
+		Ok(Item::Event(event))
 	}
 
-	fn signature_from_trait_item(method_trait_item: syn::TraitItemMethod) -> Self {
-		let constant = has_attribute(&method_trait_item.attrs, "constant");
+	fn signature_from_trait_item(method_trait_item: syn::TraitItemMethod) -> Result<Self> {
+		let constant = find_attribute(&method_trait_item.attrs, "constant");
 		let payable = has_attribute(&method_trait_item.attrs, "payable");
-		assert!(
-			!(constant && payable),
-			format!(
-				"Method {} cannot be constant and payable at the same time",
-				method_trait_item.sig.ident.to_string()
-			)
-		);
-		assert!(
-			!(method_trait_item.sig.ident.to_string() == "constructor" && constant),
-			"Constructor can't be constant"
-		);
-		Item::Signature(into_signature(
-			method_trait_item.sig.ident.clone(),
-			method_trait_item.sig,
-			constant,
-			payable,
-		))
+		let ident = method_trait_item.sig.ident.clone();
+		let modifiers = match constant {
+			Some(_) if payable => Err(Error::constant_and_payable(ident.span(), &ident)),
+			Some(attr) if ident == "constructor" => Err(Error::constant_constructor(attr.span())),
+			_ => Ok(()),
+		};
+		// The constructor is called without a selector.
+		let abi_options = AbiOptions::from_attributes(&method_trait_item.attrs).and_then(|options| {
+			match (options.name.as_ref(), options.selector.as_ref()) {
+				_ if ident != "constructor" => Ok(options),
+				(Some(&(_, span)), _) => Err(Error::misplaced_abi_option(span, "name", "the constructor")),
+				(_, Some(&(_, span))) => Err(Error::misplaced_abi_option(span, "selector", "the constructor")),
+				(None, None) => Ok(options),
+			}
+		});
+		let is_constant = constant.is_some();
+		let signature = abi_options.and_then(|abi_options| {
+			into_signature(ident, method_trait_item.sig, is_constant, payable, abi_options)
+		});
+		let (_, signature) = error::join(modifiers, signature)?;
+		Ok(Item::Signature(signature))
 	}
 
-	pub fn from_trait_item(source: syn::TraitItem) -> Self {
+	pub fn from_trait_item(source: syn::TraitItem, param_attributes: &utils::ParamAttributes) -> Result<Self> {
 		match source {
 			syn::TraitItem::Method(method_trait_item) => {
 				if method_trait_item.default.is_some() {
-					return Item::Other(syn::TraitItem::Method(method_trait_item))
+					return Ok(Item::Other(syn::TraitItem::Method(method_trait_item)))
 				}
 				if has_attribute(&method_trait_item.attrs, "event") {
-					return Self::event_from_trait_item(method_trait_item.sig)
+					return Self::event_from_trait_item(method_trait_item, param_attributes)
 				}
 				Self::signature_from_trait_item(method_trait_item)
 			},
-			trait_item => Item::Other(trait_item)
+			trait_item => Ok(Item::Other(trait_item))
 		}
 	}
 }
@@ -260,6 +549,11 @@ impl quote::ToTokens for Item {
 							let hash_bytes = keccak.as_ref().iter().map(|b| {
 								syn::Lit::Int(syn::LitInt::new(*b as u64, syn::IntSuffix::U8, Span::call_site() ))
 							});
+							let signature_topic = if event.anonymous {
+								None
+							} else {
+								Some(quote! { [#(#hash_bytes),*].into(), })
+							};
 
 							let indexed_pats = event.indexed.iter()
 								.map(|&(ref pat, _)| pat);
@@ -267,20 +561,23 @@ impl quote::ToTokens for Item {
 							let data_pats = event.data.iter()
 								.map(|&(ref pat, _)| pat);
 
-							let data_pats_count_lit = syn::Lit::Int(
-								syn::LitInt::new(event.data.len() as u64, syn::IntSuffix::Usize, Span::call_site()));
+							let data_types = event.data.iter()
+								.map(|&(_, ref ty)| ty);
 
 							quote! {
 								let topics = &[
-									[#(#hash_bytes),*].into(),
+									#signature_topic
 									#(::pwasm_abi::eth::AsLog::as_log(&#indexed_pats)),*
 								];
 
-								let mut sink = ::pwasm_abi::eth::Sink::new(#data_pats_count_lit);
-								#(sink.push(#data_pats));*;
-								let payload = sink.finalize_panicking();
+								let mut sink = ::pwasm_abi::eth::Sink::with_head_size(
+									0 #(+ <#data_types as ::pwasm_abi::eth::AbiEncode>::head_size())*
+								);
+								#(sink.push_ref(&#data_pats));*;
+								let payload = sink.finalize()?;
 
 								::pwasm_ethereum::log(topics, &payload);
+								Ok(())
 							}
 						}
 					)
@@ -1,7 +1,8 @@
 //! JSON generation
 
-use {items, utils};
+use {items, tuple, utils};
 use serde_json;
+use syn;
 
 use std::{self, io};
 
@@ -116,6 +117,30 @@ pub struct Argument {
     pub name: String,
     #[serde(rename = "type")]
     pub type_: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<Argument>,
+}
+
+impl Argument {
+    /// Returns the argument with the given name and type.
+    pub fn new(name: String, ty: &syn::Type) -> Self {
+        let canonical = utils::canonical_type(ty);
+        Argument {
+            name: name,
+            type_: utils::json_type(&canonical),
+            components: utils::components(ty).iter().map(Argument::from).collect(),
+        }
+    }
+}
+
+impl<'a> From<&'a tuple::Component> for Argument {
+    fn from(component: &tuple::Component) -> Self {
+        Argument {
+            name: component.name.clone(),
+            type_: utils::json_type(&component.canonical),
+            components: component.components.iter().map(Argument::from).collect(),
+        }
+    }
 }
 
 #[derive(Serialize, Debug)]
@@ -133,6 +158,8 @@ pub enum AbiEntry {
     Function(FunctionEntry),
     #[serde(rename = "constructor")]
     Constructor(ConstructorEntry),
+    #[serde(rename = "error")]
+    Error(ErrorEntry),
 }
 
 #[derive(Serialize, Debug)]
@@ -140,13 +167,35 @@ pub struct EventInput {
     pub name: String,
     #[serde(rename = "type")]
     pub type_: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<Argument>,
     pub indexed: bool,
 }
 
+impl EventInput {
+    /// Returns the event input with the given name and type.
+    pub fn new(name: String, ty: &syn::Type, indexed: bool) -> Self {
+        let argument = Argument::new(name, ty);
+        EventInput {
+            name: argument.name,
+            type_: argument.type_,
+            components: argument.components,
+            indexed: indexed,
+        }
+    }
+}
+
 #[derive(Serialize, Debug)]
 pub struct EventEntry {
     pub name: String,
     pub inputs: Vec<EventInput>,
+    pub anonymous: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ErrorEntry {
+    pub name: String,
+    pub inputs: Vec<Argument>,
 }
 
 #[derive(Serialize, Debug)]
@@ -155,13 +204,23 @@ pub struct Abi(pub Vec<AbiEntry>);
 impl<'a> From<&'a items::Interface> for Abi {
     fn from(intf: &items::Interface) -> Self {
         let mut result = Vec::new();
+        let mut errors: Vec<ErrorEntry> = Vec::new();
         for item in intf.items() {
             match *item {
                 items::Item::Event(ref event) => result.push(AbiEntry::Event(event.into())),
-                items::Item::Signature(ref signature) => result.push(AbiEntry::Function(signature.into())),
+                items::Item::Signature(ref signature) => {
+                    result.push(AbiEntry::Function(signature.into()));
+                    // Custom errors shared by several signatures are listed once.
+                    if let Some(items::Revert::Custom(ref custom_error)) = signature.revert {
+                        if !errors.iter().any(|error| error.name == custom_error.name) {
+                            errors.push(custom_error.into());
+                        }
+                    }
+                },
                 _ => {}
             }
         }
+        result.extend(errors.into_iter().map(AbiEntry::Error));
 
         if let Some(constructor) = intf.constructor() {
             result.push(AbiEntry::Constructor(FunctionEntry::from(constructor).into()));
@@ -173,29 +232,19 @@ impl<'a> From<&'a items::Interface> for Abi {
 
 impl<'a> From<&'a items::Event> for EventEntry {
     fn from(item: &items::Event) -> Self {
+        // Inputs are listed in declaration order, which the data of the log is decoded in.
         EventEntry {
-            name: item.name.to_string(),
-            inputs: item.indexed
-                .iter()
-                .map(|&(ref pat, ref ty)|
-                    EventInput {
-                        name: quote! { #pat }.to_string(),
-                        type_: utils::canonicalize_type(ty),
-                        indexed: true,
-                    }
-                )
-                .chain(
-                    item.data
+            name: item.abi_name.clone(),
+            inputs: utils::iter_signature(&item.method_sig)
+                .map(|(pat, ty)| {
+                    let name = quote! { #pat }.to_string();
+                    let indexed = item.indexed
                         .iter()
-                        .map(|&(ref pat, ref ty)|
-                            EventInput {
-                                name: quote! { #pat }.to_string(),
-                                type_: utils::canonicalize_type(ty),
-                                indexed: false,
-                            }
-                        )
-                    )
+                        .any(|&(ref indexed_pat, _)| quote! { #indexed_pat }.to_string() == name);
+                    EventInput::new(name, &ty, indexed)
+                })
                 .collect(),
+            anonymous: item.anonymous,
         }
     }
 }
@@ -203,20 +252,15 @@ impl<'a> From<&'a items::Event> for EventEntry {
 impl<'a> From<&'a items::Signature> for FunctionEntry {
     fn from(item: &items::Signature) -> Self {
         FunctionEntry {
-            name: item.name.to_string(),
+            name: item.abi_name.clone(),
             arguments: item.arguments
                 .iter()
-                .map(|&(ref pat, ref ty)|
-                    Argument {
-                        name: quote! { #pat }.to_string(),
-                        type_: utils::canonicalize_type(ty),
-                    }
-                )
+                .map(|&(ref pat, ref ty)| Argument::new(quote! { #pat }.to_string(), ty))
                 .collect(),
             outputs: item.return_types
                 .iter()
                 .enumerate()
-                .map(|(idx, ty)| Argument { name: format!("returnValue{}", idx), type_: utils::canonicalize_type(ty) })
+                .map(|(idx, ty)| Argument::new(format!("returnValue{}", idx), ty))
                 .collect(),
             constant: item.is_constant,
 			payable: item.is_payable,
@@ -224,6 +268,15 @@ impl<'a> From<&'a items::Signature> for FunctionEntry {
     }
 }
 
+impl<'a> From<&'a items::CustomError> for ErrorEntry {
+    fn from(item: &items::CustomError) -> Self {
+        ErrorEntry {
+            name: item.name.clone(),
+            inputs: item.components.iter().map(Argument::from).collect(),
+        }
+    }
+}
+
 impl From<FunctionEntry> for ConstructorEntry {
     fn from(func: FunctionEntry) -> Self {
         ConstructorEntry { arguments: func.arguments }
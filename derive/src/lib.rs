@@ -1,6 +1,6 @@
 //! Ethereum (Solidity) derivation for rust contracts (compiled to wasm or otherwise)
 
-#![recursion_limit = "128"]
+#![recursion_limit = "256"]
 #![deny(unused)]
 
 extern crate proc_macro;
@@ -26,11 +26,15 @@ mod error;
 mod items;
 mod utils;
 mod json;
+mod tuple;
+#[cfg(test)]
+mod tests;
 
 use proc_macro2::{Span};
 use json::write_json_abi;
 use items::Item;
 use error::{Result, Error};
+use syn::spanned::Spanned;
 
 /// Arguments given to the `eth_abi` attribute macro.
 struct Args {
@@ -38,33 +42,38 @@ struct Args {
 	endpoint_name: String,
 	/// The optional name of the client.
 	client_name: Option<String>,
+	/// Whether payloads are decoded in strict mode, given by a trailing `strict`.
+	strict: bool,
 }
 
 impl Args {
 	/// Extracts `eth_abi` argument information from the given `syn::AttributeArgs`.
 	pub fn from_attribute_args(attr_args: syn::AttributeArgs) -> Result<Args> {
-		if attr_args.len() == 0 || attr_args.len() > 2 {
-			return Err(Error::invalid_number_of_arguments(0));
+		if attr_args.len() == 0 || attr_args.len() > 3 {
+			return Err(Error::invalid_number_of_arguments(attr_args.len()));
 		}
-		let endpoint_name =
-			if let syn::NestedMeta::Meta(syn::Meta::Word(ident)) = attr_args.get(0).unwrap() {
+		let mut names = error::collect(attr_args.iter().enumerate().map(|(index, meta)| {
+			if let syn::NestedMeta::Meta(syn::Meta::Word(ident)) = meta {
 				Ok(ident.to_string())
 			} else {
-				Err(Error::malformatted_argument(0))
-			}?;
-		let client_name = attr_args
-			.get(1)
-			.map(|meta| {
-				if let syn::NestedMeta::Meta(syn::Meta::Word(ident)) = meta {
-					Ok(ident.to_string())
-				} else {
-					Err(Error::malformatted_argument(1))
-				}
-			})
-			.map(|meta| meta.unwrap());
+				Err(Error::malformatted_argument(meta.span(), index))
+			}
+		}))?;
+		// `strict` can only follow the endpoint name, which may be `strict` itself.
+		let strict = names.len() > 1 && names.last().map_or(false, |name| name == "strict");
+		if strict {
+			names.pop();
+		}
+		if names.len() > 2 {
+			return Err(Error::invalid_number_of_arguments(attr_args.len()));
+		}
+		let mut names = names.into_iter();
+		let endpoint_name = names.next().expect("the number of arguments is checked above");
+		let client_name = names.next();
 		Ok(Args {
 			endpoint_name,
 			client_name,
+			strict,
 		})
 	}
 
@@ -77,6 +86,11 @@ impl Args {
 	pub fn client_name(&self) -> Option<&str> {
 		self.client_name.as_ref().map(|s| s.as_str())
 	}
+
+	/// Returns whether payloads are decoded in strict mode.
+	pub fn strict(&self) -> bool {
+		self.strict
+	}
 }
 
 /// Derive of the Ethereum/Solidity ABI for the given trait interface.
@@ -85,6 +99,9 @@ impl Args {
 /// implementation. The seconds parameter is optional and represents the
 /// identifier of the generated client implementation.
 ///
+/// The generated code encodes into buffers on the heap, so it needs
+/// the `alloc` feature of `pwasm-abi`.
+///
 /// # System Description
 ///
 /// ## Endpoint
@@ -96,6 +113,20 @@ impl Args {
 /// Opposite of an endpoint that allows users (clients) to build up queries
 /// in the form of a payload to functions of a contract by a generated interface.
 ///
+/// The host copies the output of a call into a buffer of the client without
+/// reporting its length, so the size of dynamic output has to be bounded upfront.
+/// The bound is `pwasm_abi::eth::DEFAULT_RETURN_CAPACITY` unless set by
+/// `Client::new(address).return_capacity(n)`. Output of dynamic size
+/// exceeding it is reported as `CallError::Truncated`.
+///
+/// Methods of the client panic if the call fails, except methods returning
+/// `Result<T, E>`. If `E` is a string, they return the reason the called
+/// contract reverted with, or the description of any other failure. A `&str`
+/// reason is leaked to live for the rest of the execution. If `E` is a custom
+/// error, they return the error the called contract reverted with and panic
+/// on other failures. The `try_` methods of the client report every failure
+/// as `CallError`.
+///
 /// # Example: Using just one argument
 ///
 /// ```
@@ -118,35 +149,141 @@ impl Args {
 /// Creates an endpoint implementation named `Endpoint2` and a
 /// client implementation named `Client2` for the interface
 /// defined in the `Contract2` trait.
+///
+/// # Events
+///
+/// Methods with the `#[event]` attribute emit a log of their parameters.
+/// Parameters with the `#[indexed]` attribute, or whose name starts with
+/// `indexed_`, are stored as topics following the event signature, up to 3
+/// per event. Events declared as `#[event(anonymous)]` leave out the
+/// signature topic and can have up to 4 indexed parameters.
+///
+/// The method emitting the event returns `Result<(), pwasm_abi::eth::SinkError>`,
+/// which is an error if the `AbiEncode` implementation of a parameter is inconsistent.
+///
+/// ```ignore
+/// #[event]
+/// fn transfer(&mut self, #[indexed] from: Address, #[indexed] to: Address, value: U256);
+/// ```
+///
+/// # Solidity names and selectors
+///
+/// The `abi` attribute gives the name of a method or event in the abi instead of
+/// deriving it from the Rust method. This binds overloaded Solidity functions to
+/// methods of different names. The selector of a method can be given as well,
+/// which has to match the one derived from its name and parameter types.
+///
+/// ```ignore
+/// #[abi(name = "safeTransferFrom")]
+/// fn safe_transfer_from(&mut self, from: Address, to: Address, token_id: U256);
+/// #[abi(name = "safeTransferFrom")]
+/// fn safe_transfer_from_with_data(&mut self, from: Address, to: Address, token_id: U256, data: Vec<u8>);
+/// #[abi(name = "balanceOf", selector = 0x70a08231)]
+/// fn balance_of(&mut self, owner: Address) -> U256;
+/// ```
+///
+/// # Strict decoding
+///
+/// A trailing `strict` argument makes the generated code decode payloads with
+/// `pwasm_abi::eth::Stream::new_strict`, accepting only their canonical encoding.
+/// Payloads of the endpoint and data of logs continuing after the decoded values
+/// are rejected as well, as `DispatchError::Decode` and `ErrorKind::TrailingBytes`
+/// respectively. Output of calls of the client is followed by the zeros filling
+/// its buffer, so it is not checked for trailing bytes.
+///
+/// ```
+/// # #![feature(custom_attribute)]
+/// #[eth_abi(Endpoint3, Client3, strict)]
+/// trait Contract3 { }
+/// ```
+///
+/// # Borrowed parameters
+///
+/// Parameters of type `&[u8]`, `&str` and `ArrayIter<T>` are decoded
+/// by the endpoint without copying the payload, as `bytes`, `string`
+/// and `T[]` respectively.
+///
+/// # Structs
+///
+/// Structs deriving `AbiType` are encoded as tuples of their fields. The fields
+/// are looked up by the name of the struct while the interface is expanded, so
+/// the struct has to derive `AbiType` earlier in the same crate, and structs of
+/// the same name have to have the same fields. See the `AbiType` derive.
 #[proc_macro_attribute]
 pub fn eth_abi(
 	args: proc_macro::TokenStream,
 	input: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
 	let args_toks = parse_macro_input!(args as syn::AttributeArgs);
+	let (input, param_attributes) = utils::ParamAttributes::strip(input.into());
+	let input: proc_macro::TokenStream = input.into();
 	let input_toks = parse_macro_input!(input as syn::Item);
 
-	let output = match impl_eth_abi(args_toks, input_toks) {
+	let output = match impl_eth_abi(args_toks, input_toks, &param_attributes) {
 		Ok(output) => output,
-		Err(err) => panic!("[eth_abi] encountered error: {}", err),
+		Err(err) => err.to_compile_error(),
 	};
 
 	output.into()
 }
 
+/// Derive of `pwasm_abi::eth::AbiEncode` and `pwasm_abi::eth::AbiDecode`
+/// for structs with named fields.
+///
+/// The struct is encoded as a Solidity ABI v2 tuple of its fields in
+/// declaration order, e.g. `(address,uint256)` for the struct below.
+/// With the `alloc` feature of `pwasm-abi`, `pwasm_abi::eth::AbiEncodePacked`
+/// and `pwasm_abi::eth::AsLog` are derived as well, so the struct can be
+/// an indexed parameter of events.
+///
+/// # Note
+///
+/// Selectors and the JSON abi of `eth_abi` interfaces are computed during
+/// macro expansion, so the fields of structs are only known to the interfaces
+/// expanded after the derive in the same crate. This applies to structs used
+/// as parameters and return values as well as to custom errors:
+///
+/// - The struct has to be declared before any `eth_abi` interface using it.
+/// - Structs of other crates can't be used in `eth_abi` interfaces.
+/// - Structs are identified by their name without the module path, so structs
+///   deriving `AbiType` with the same name have to have the same fields.
+///
+/// # Example
+///
+/// ```ignore
+/// #[derive(AbiType)]
+/// pub struct Position {
+///     owner: Address,
+///     amount: U256,
+/// }
+/// ```
+#[proc_macro_derive(AbiType)]
+pub fn derive_abi_type(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+	let input_toks = parse_macro_input!(input as syn::DeriveInput);
+	let output = match tuple::Tuple::from_derive_input(input_toks) {
+		Ok(tuple) => quote! { #tuple },
+		Err(err) => err.to_compile_error(),
+	};
+	output.into()
+}
+
 /// Implementation of `eth_abi`.
 ///
 /// This convenience function is mainly used to better handle the results of token stream.
-fn impl_eth_abi(args: syn::AttributeArgs, input: syn::Item) -> Result<proc_macro2::TokenStream> {
+fn impl_eth_abi(
+	args: syn::AttributeArgs,
+	input: syn::Item,
+	param_attributes: &utils::ParamAttributes,
+) -> Result<proc_macro2::TokenStream> {
 	let args = Args::from_attribute_args(args)?;
-	let intf = items::Interface::from_item(input);
+	let intf = items::Interface::from_item(input, param_attributes)?;
 
 	write_json_abi(&intf)?;
 
 	match args.client_name() {
-		None => generate_eth_endpoint_wrapper(&intf, args.endpoint_name()),
+		None => generate_eth_endpoint_wrapper(&intf, args.endpoint_name(), args.strict()),
 		Some(client_name) => {
-			generate_eth_endpoint_and_client_wrapper(&intf, args.endpoint_name(), client_name)
+			generate_eth_endpoint_and_client_wrapper(&intf, args.endpoint_name(), client_name, args.strict())
 		}
 	}
 }
@@ -155,6 +292,7 @@ fn impl_eth_abi(args: syn::AttributeArgs, input: syn::Item) -> Result<proc_macro
 fn generate_eth_endpoint_wrapper(
 	intf: &items::Interface,
 	endpoint_name: &str,
+	strict: bool,
 ) -> Result<proc_macro2::TokenStream> {
 	// FIXME: Code duplication with `generate_eth_endpoint_and_client_wrapper`
 	//        We might want to fix this, however it is not critical.
@@ -164,8 +302,13 @@ fn generate_eth_endpoint_wrapper(
 	let mod_name_ident = syn::Ident::new(&mod_name, Span::call_site());
 	// FIXME: <<<
 
-	let endpoint_toks = generate_eth_endpoint(endpoint_name, intf);
+	let endpoint_toks = generate_eth_endpoint(endpoint_name, intf, strict);
 	let endpoint_ident = syn::Ident::new(endpoint_name, Span::call_site());
+	let events_toks = generate_eth_events(intf, strict);
+	let events_use = events_toks.as_ref().map(|_| {
+		let events_ident = events_mod_ident(intf);
+		quote! { pub use self::#mod_name_ident::#events_ident; }
+	});
 
 	Ok(quote! {
 		#intf
@@ -174,10 +317,14 @@ fn generate_eth_endpoint_wrapper(
 			extern crate pwasm_ethereum;
 			extern crate pwasm_abi;
 			use pwasm_abi::types::{H160, H256, U256, Address, Vec, String};
+			#[allow(unused_imports)]
+			use super::*;
 			use super::#name_ident_use;
 			#endpoint_toks
+			#events_toks
 		}
 		pub use self::#mod_name_ident::#endpoint_ident;
+		#events_use
 	})
 }
 
@@ -186,6 +333,7 @@ fn generate_eth_endpoint_and_client_wrapper(
 	intf: &items::Interface,
 	endpoint_name: &str,
 	client_name: &str,
+	strict: bool,
 ) -> Result<proc_macro2::TokenStream> {
 
 	// FIXME: Code duplication with `generate_eth_endpoint_and_client_wrapper`
@@ -196,10 +344,15 @@ fn generate_eth_endpoint_and_client_wrapper(
 	let mod_name_ident = syn::Ident::new(&mod_name, Span::call_site());
 	// FIXME: <<<
 
-	let endpoint_toks = generate_eth_endpoint(endpoint_name, &intf);
-	let client_toks = generate_eth_client(client_name, &intf);
+	let endpoint_toks = generate_eth_endpoint(endpoint_name, &intf, strict);
+	let client_toks = generate_eth_client(client_name, &intf, strict);
 	let endpoint_name_ident = syn::Ident::new(endpoint_name, Span::call_site());
 	let client_name_ident = syn::Ident::new(&client_name, Span::call_site());
+	let events_toks = generate_eth_events(intf, strict);
+	let events_use = events_toks.as_ref().map(|_| {
+		let events_ident = events_mod_ident(intf);
+		quote! { pub use self::#mod_name_ident::#events_ident; }
+	});
 
 	Ok(quote! {
 		#intf
@@ -208,16 +361,162 @@ fn generate_eth_endpoint_and_client_wrapper(
 			extern crate pwasm_ethereum;
 			extern crate pwasm_abi;
 			use pwasm_abi::types::{H160, H256, U256, Address, Vec, String};
+			#[allow(unused_imports)]
+			use super::*;
 			use super::#name_ident_use;
 			#endpoint_toks
 			#client_toks
+			#events_toks
 		}
 		pub use self::#mod_name_ident::#endpoint_name_ident;
 		pub use self::#mod_name_ident::#client_name_ident;
+		#events_use
 	})
 }
 
-fn generate_eth_client(client_name: &str, intf: &items::Interface) -> proc_macro2::TokenStream {
+/// Returns the identifier of the module holding the typed events of the interface,
+/// e.g. `token_contract_events` for `TokenContract`.
+fn events_mod_ident(intf: &items::Interface) -> syn::Ident {
+	syn::Ident::new(&format!("{}_events", utils::to_snake_case(intf.name())), Span::call_site())
+}
+
+/// Returns the constructor of `pwasm_abi::eth::Stream` decoding payloads
+/// in strict or lenient mode.
+fn stream_constructor(strict: bool) -> syn::Ident {
+	syn::Ident::new(if strict { "new_strict" } else { "new" }, Span::call_site())
+}
+
+/// Returns the statement rejecting the rest of a payload following the decoded values
+/// in strict mode, which is a no-op in lenient mode.
+fn stream_finish_toks(strict: bool) -> Option<proc_macro2::TokenStream> {
+	if strict {
+		Some(quote! { stream.finish()?; })
+	} else {
+		None
+	}
+}
+
+/// Generates a struct for every event of the interface that can be decoded from a log.
+///
+/// Indexed parameters of dynamic or array types are only available as the hash in their topic.
+/// Logs of anonymous events can't be told apart by their topics, so they are decoded
+/// as any event with a matching number of topics.
+///
+/// Returns `None` if the interface has no events.
+fn generate_eth_events(intf: &items::Interface, strict: bool) -> Option<proc_macro2::TokenStream> {
+	let events: Vec<proc_macro2::TokenStream> = intf.items().iter().filter_map(|item| {
+		match *item {
+			Item::Event(ref event) => Some(generate_eth_event(event, strict)),
+			_ => None,
+		}
+	}).collect();
+
+	if events.is_empty() {
+		return None;
+	}
+
+	let events_ident = events_mod_ident(intf);
+
+	Some(quote! {
+		pub mod #events_ident {
+			#[allow(unused_imports)]
+			use super::*;
+			#(#events)*
+		}
+	})
+}
+
+fn generate_eth_event(event: &items::Event, strict: bool) -> proc_macro2::TokenStream {
+	let stream_new = stream_constructor(strict);
+	let stream_finish = stream_finish_toks(strict);
+	let struct_ident = syn::Ident::new(&utils::to_camel_case(&event.name.to_string()), Span::call_site());
+	let hash_bytes = utils::keccak(event.canonical.as_bytes()).as_ref().iter().map(|b| {
+		syn::Lit::Int(syn::LitInt::new(*b as u64, syn::IntSuffix::U8, Span::call_site()))
+	}).collect::<Vec<_>>();
+	// Anonymous events have no signature topic preceding the indexed parameters.
+	let first_topic = if event.anonymous { 0 } else { 1 };
+	let topics_count_literal = syn::Lit::Int(syn::LitInt::new(
+		(first_topic + event.indexed.len()) as u64, syn::IntSuffix::Usize, Span::call_site()));
+
+	let mut fields = Vec::new();
+	let mut field_decodes = Vec::new();
+	let mut topic_index = first_topic;
+	for (index, (pat, ty)) in utils::iter_signature(&event.method_sig).enumerate() {
+		let index_literal = syn::Lit::Int(
+			syn::LitInt::new(index as u64, syn::IntSuffix::Usize, Span::call_site()));
+		let field_ident = match pat {
+			syn::Pat::Ident(ref pat_ident) => pat_ident.ident.clone(),
+			_ => unreachable!("parameters of events are validated to be identifiers"),
+		};
+		// Indexed parameters are stored as topics in the order of declaration
+		// following the event signature, all others are encoded in the log data.
+		let (decode, field_ty) = if event.indexed.iter().any(|&(ref indexed_pat, _)| *indexed_pat == pat) {
+			let topic_index_literal = syn::Lit::Int(
+				syn::LitInt::new(topic_index as u64, syn::IntSuffix::Usize, Span::call_site()));
+			topic_index += 1;
+			if utils::is_hashed_in_topic(&utils::canonical_type(&ty)) {
+				// Only the hash of dynamic values and arrays is available.
+				(quote! { topics[#topic_index_literal] }, quote! { H256 })
+			} else {
+				(quote! {
+					::pwasm_abi::eth::Stream::#stream_new(topics[#topic_index_literal].as_ref()).pop::<#ty>()
+						.map_err(|error| error.at_argument(#index_literal))?
+				}, quote! { #ty })
+			}
+		} else {
+			(quote! { stream.pop::<#ty>().map_err(|error| error.at_argument(#index_literal))? }, quote! { #ty })
+		};
+		fields.push(quote! { pub #field_ident: #field_ty });
+		field_decodes.push(quote! { #field_ident: #decode });
+	}
+
+	let (signature, signature_check) = if event.anonymous {
+		(None, None)
+	} else {
+		(
+			Some(quote! {
+				/// The Keccak hash of the event signature stored as the first log topic.
+				pub const SIGNATURE: [u8; 32] = [#(#hash_bytes),*];
+			}),
+			Some(quote! {
+				if topics[0].as_ref() != &Self::SIGNATURE[..] {
+					return Err(::pwasm_abi::eth::ErrorKind::InvalidEventSignature.into());
+				}
+			}),
+		)
+	};
+
+	quote! {
+		pub struct #struct_ident {
+			#(#fields),*
+		}
+
+		impl #struct_ident {
+			#signature
+
+			/// Decodes the event from the topics and data of a log.
+			#[allow(unused_mut)]
+			pub fn decode_log(topics: &[H256], data: &[u8]) -> Result<Self, ::pwasm_abi::eth::Error> {
+				if topics.len() != #topics_count_literal {
+					return Err(::pwasm_abi::eth::ErrorKind::InvalidTopics.into());
+				}
+				#signature_check
+				let mut stream = ::pwasm_abi::eth::Stream::#stream_new(data);
+				let event = #struct_ident {
+					#(#field_decodes),*
+				};
+				#stream_finish
+				Ok(event)
+			}
+		}
+	}
+}
+
+fn generate_eth_client(client_name: &str, intf: &items::Interface, strict: bool) -> proc_macro2::TokenStream {
+	// The output of a call fills a buffer of the client, so it is followed
+	// by zeros and can't be checked for trailing bytes.
+	let stream_new = stream_constructor(strict);
+
 	let client_ctor = intf.constructor().map(
 		|signature| utils::produce_signature(
 			&signature.name,
@@ -230,35 +529,103 @@ fn generate_eth_client(client_name: &str, intf: &items::Interface) -> proc_macro
 		)
 	);
 
+	/// Returns the expression encoding the payload of the call into a `Result<Vec<u8>, SinkError>`.
+	fn call_payload_toks(signature: &items::Signature) -> proc_macro2::TokenStream {
+		let hash_literal = syn::Lit::Int(
+			syn::LitInt::new(signature.hash as u64, syn::IntSuffix::U32, Span::call_site()));
+		let argument_push: Vec<proc_macro2::TokenStream> = utils::iter_signature(&signature.method_sig)
+			.map(|(pat, _)| quote! { sink.push_ref(&#pat); })
+			.collect();
+		let argument_types = signature.arguments.iter().map(|&(_, ref ty)| ty);
+		quote! {
+			{
+				let mut sink = pwasm_abi::eth::Sink::with_selector(#hash_literal);
+				sink.reserve(0 #(+ <#argument_types as pwasm_abi::eth::AbiEncode>::head_size())*);
+				#(#argument_push)*
+				sink.finalize()
+			}
+		}
+	}
+
+	/// Returns the expressions whether the call output is fixed and of its number of bytes if it is.
+	fn fixed_result_len_toks(signature: &items::Signature) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+		let return_types = &signature.return_types;
+		(
+			quote! { true #(& <#return_types as pwasm_abi::eth::AbiEncode>::IS_FIXED)* },
+			quote! { 0 #(+ <#return_types as pwasm_abi::eth::AbiEncode>::head_size())* },
+		)
+	}
+
 	let calls: Vec<proc_macro2::TokenStream> = intf.items().iter().filter_map(|item| {
 		match *item {
-			Item::Signature(ref signature)  => {
-				let hash_literal = syn::Lit::Int(
-					syn::LitInt::new(signature.hash as u64, syn::IntSuffix::U32, Span::call_site()));
-				let argument_push: Vec<proc_macro2::TokenStream> = utils::iter_signature(&signature.method_sig)
-					.map(|(pat, _)| quote! { sink.push(#pat); })
-					.collect();
-				let argument_count_literal = syn::Lit::Int(
-					syn::LitInt::new(argument_push.len() as u64, syn::IntSuffix::Usize, Span::call_site()));
-
-				let result_instance = match signature.method_sig.decl.output {
-					syn::ReturnType::Default => quote!{
-						let mut result = Vec::new();
+			Item::Signature(ref signature) if signature.revert.is_some() => {
+				let try_ident = syn::Ident::new(&format!("try_{}", signature.name), Span::call_site());
+				let args = utils::iter_signature(&signature.method_sig).map(|(pat, _)| pat);
+				// Reasons are returned as a `String` or a leaked `str` reference.
+				let owned_reason = match signature.method_sig.decl.output {
+					syn::ReturnType::Type(_, ref ty) => utils::result_types(ty)
+						.map_or(false, |(_, err_type)| match err_type {
+							syn::Type::Path(_) => true,
+							_ => false,
+						}),
+					syn::ReturnType::Default => false,
+				};
+				// Every failure is returned as a reason, while only custom errors
+				// of the method can be returned as such and other failures panic.
+				let call_error = match signature.revert {
+					Some(items::Revert::Custom(_)) => quote! {
+						Err(pwasm_abi::eth::CallError::Custom(error)) => Err(error),
+						Err(error) => panic!("{}", error),
+					},
+					_ if owned_reason => quote! {
+						Err(error) => Err(error.into_reason()),
 					},
-					syn::ReturnType::Type(_, _) => quote!{
-						let mut result = [0u8; 32];
+					_ => quote! {
+						Err(error) => Err(error.into_static_reason()),
 					},
 				};
+				Some(utils::produce_signature(
+					&signature.name,
+					&signature.method_sig,
+					quote!{
+						match self.#try_ident(#(#args),*) {
+							Ok(output) => Ok(output),
+							#call_error
+						}
+					}
+				))
+			},
+			Item::Signature(ref signature)  => {
+				let call_payload = call_payload_toks(signature);
+				let (is_fixed, fixed_len) = fixed_result_len_toks(signature);
+				let result_len = quote! {
+					if #is_fixed { #fixed_len } else { self.return_capacity }
+				};
+
+				let result_instance = quote!{
+					let mut result = Vec::new();
+					result.resize(#result_len, 0u8);
+				};
 
-				let result_pop = match signature.method_sig.decl.output {
-					syn::ReturnType::Default => None,
-					syn::ReturnType::Type(_, _) => Some(
+				// Multiple return values are encoded as consecutive values
+				// in the same way as the arguments of a call.
+				let result_decode = signature.output.as_ref().map(|ty| match *ty {
+					syn::Type::Tuple(ref tuple_type) => {
+						let return_types = tuple_type.elems.iter();
 						quote!{
-							let mut stream = pwasm_abi::eth::Stream::new(&result);
-							stream.pop().expect("failed decode call output")
+							(#(stream.pop::<#return_types>().unwrap_or_else(|error| panic!("{}", pwasm_abi::eth::CallError::<()>::from_output(error, &result, result.len()))),)*)
 						}
-					),
-				};
+					},
+					_ => quote!{
+						stream.pop().unwrap_or_else(|error| panic!("{}", pwasm_abi::eth::CallError::<()>::from_output(error, &result, result.len())))
+					},
+				});
+				let result_pop = result_decode.map(|result_decode| {
+					quote!{
+						let mut stream = pwasm_abi::eth::Stream::#stream_new(&result);
+						#result_decode
+					}
+				});
 
 				Some(utils::produce_signature(
 					&signature.name,
@@ -266,21 +633,13 @@ fn generate_eth_client(client_name: &str, intf: &items::Interface) -> proc_macro
 					quote!{
 						#![allow(unused_mut)]
 						#![allow(unused_variables)]
-						let mut payload = Vec::with_capacity(4 + #argument_count_literal * 32);
-						payload.push((#hash_literal >> 24) as u8);
-						payload.push((#hash_literal >> 16) as u8);
-						payload.push((#hash_literal >> 8) as u8);
-						payload.push(#hash_literal as u8);
-
-						let mut sink = pwasm_abi::eth::Sink::new(#argument_count_literal);
-						#(#argument_push)*
-
-						sink.drain_to(&mut payload);
+						let payload = #call_payload
+							.unwrap_or_else(|error| panic!("{}", pwasm_abi::eth::CallError::<()>::Encode(error)));
 
 						#result_instance
 
 						pwasm_ethereum::call(self.gas.unwrap_or(200000), &self.address, self.value.clone().unwrap_or(U256::zero()), &payload, &mut result[..])
-							.expect("Call failed; todo: allow handling inside contracts");
+							.expect("Call failed; use the try_ methods of the client to handle failures");
 
 						#result_pop
 					}
@@ -300,6 +659,71 @@ fn generate_eth_client(client_name: &str, intf: &items::Interface) -> proc_macro
 		}
 	}).collect();
 
+	// Fallible versions of the calls reporting failures instead of panicking.
+	let try_calls: Vec<proc_macro2::TokenStream> = intf.items().iter().filter_map(|item| {
+		let signature = match *item {
+			Item::Signature(ref signature) => signature,
+			_ => return None,
+		};
+		let call_payload = call_payload_toks(signature);
+		// The output buffer also has to hold the revert data of a failed call.
+		let (is_fixed, fixed_len) = fixed_result_len_toks(signature);
+		let result_len = quote! {
+			if #is_fixed && #fixed_len > self.return_capacity { #fixed_len } else { self.return_capacity }
+		};
+		let (call_error, from_revert) = match signature.revert {
+			Some(items::Revert::Custom(ref custom_error)) => {
+				let err_type = &custom_error.ty;
+				let selector_literal = syn::Lit::Int(syn::LitInt::new(
+					custom_error.selector as u64, syn::IntSuffix::U32, Span::call_site()));
+				(
+					quote! { pwasm_abi::eth::CallError<#err_type> },
+					quote! { pwasm_abi::eth::CallError::from_custom_revert(#selector_literal, &result) },
+				)
+			},
+			_ => (
+				quote! { pwasm_abi::eth::CallError },
+				quote! { pwasm_abi::eth::CallError::from_revert(&result) },
+			),
+		};
+		let output = match signature.output {
+			Some(ref ty) => quote! { #ty },
+			None => quote! { () },
+		};
+		let result_decode = match signature.output {
+			Some(syn::Type::Tuple(ref tuple_type)) => {
+				let return_types = tuple_type.elems.iter();
+				quote! {
+					(#(stream.pop::<#return_types>().map_err(|error| pwasm_abi::eth::CallError::from_output(error, &result, result.len()))?,)*)
+				}
+			},
+			Some(ref ty) => quote! {
+				stream.pop::<#ty>().map_err(|error| pwasm_abi::eth::CallError::from_output(error, &result, result.len()))?
+			},
+			None => quote! { () },
+		};
+		let try_ident = syn::Ident::new(&format!("try_{}", signature.name), Span::call_site());
+		let args = utils::iter_signature(&signature.method_sig).map(|(pat, ty)| quote! { #pat: #ty });
+
+		Some(quote! {
+			#[allow(unused_mut)]
+			#[allow(unused_variables)]
+			pub fn #try_ident(&mut self, #(#args),*) -> Result<#output, #call_error> {
+				let payload = #call_payload.map_err(pwasm_abi::eth::CallError::Encode)?;
+
+				let mut result = Vec::new();
+				result.resize(#result_len, 0u8);
+
+				if pwasm_ethereum::call(self.gas.unwrap_or(200000), &self.address, self.value.clone().unwrap_or(U256::zero()), &payload, &mut result[..]).is_err() {
+					return Err(#from_revert);
+				}
+
+				let mut stream = pwasm_abi::eth::Stream::#stream_new(&result);
+				Ok(#result_decode)
+			}
+		})
+	}).collect();
+
 	let client_ident = syn::Ident::new(client_name, Span::call_site());
 	let name_ident = syn::Ident::new(intf.name(), Span::call_site());
 
@@ -308,6 +732,7 @@ fn generate_eth_client(client_name: &str, intf: &items::Interface) -> proc_macro
 			gas: Option<u64>,
 			address: Address,
 			value: Option<U256>,
+			return_capacity: usize,
 		}
 
 		impl #client_ident {
@@ -316,6 +741,7 @@ fn generate_eth_client(client_name: &str, intf: &items::Interface) -> proc_macro
 					gas: None,
 					address: address,
 					value: None,
+					return_capacity: pwasm_abi::eth::DEFAULT_RETURN_CAPACITY,
 				}
 			}
 
@@ -328,6 +754,13 @@ fn generate_eth_client(client_name: &str, intf: &items::Interface) -> proc_macro
 				self.value = Some(val);
 				self
 			}
+
+			pub fn return_capacity(mut self, return_capacity: usize) -> Self {
+				self.return_capacity = return_capacity;
+				self
+			}
+
+			#(#try_calls)*
 		}
 
 		impl #name_ident for #client_ident {
@@ -337,27 +770,51 @@ fn generate_eth_client(client_name: &str, intf: &items::Interface) -> proc_macro
 	}
 }
 
-fn generate_eth_endpoint(endpoint_name: &str, intf: &items::Interface) -> proc_macro2::TokenStream {
+fn generate_eth_endpoint(endpoint_name: &str, intf: &items::Interface, strict: bool) -> proc_macro2::TokenStream {
 	fn check_value_if_payable_toks(is_payable: bool) -> proc_macro2::TokenStream {
 		if is_payable {
 			return quote!{}
 		}
 		quote!{
 			if pwasm_ethereum::value() > 0.into() {
-				panic!("Unable to accept value in non-payable constructor call");
+				return Err(pwasm_abi::eth::DispatchError::ValueNotAccepted);
 			}
 		}
 	}
 
+	/// Returns the statements decoding the arguments of the method from `stream`
+	/// and the identifiers they are bound to.
+	fn arg_pops_toks(signature: &items::Signature) -> (proc_macro2::TokenStream, Vec<syn::Ident>) {
+		let idents: Vec<syn::Ident> = (0..signature.arguments.len())
+			.map(|index| syn::Ident::new(&format!("arg{}", index), Span::call_site()))
+			.collect();
+		let pops = signature.arguments.iter().zip(idents.iter()).enumerate().map(|(index, (&(_, ref ty), ident))| {
+			let index_literal = syn::Lit::Int(
+				syn::LitInt::new(index as u64, syn::IntSuffix::Usize, Span::call_site()));
+			quote! {
+				let #ident = stream.pop::<#ty>().map_err(|error| pwasm_abi::eth::DispatchError::ArgumentDecode {
+					index: #index_literal,
+					error: error.at_argument(#index_literal),
+				})?;
+			}
+		});
+		(quote! { #(#pops)* }, idents)
+	}
+
+	let stream_new = stream_constructor(strict);
+	let stream_finish = stream_finish_toks(strict);
+
 	let ctor_branch = intf.constructor().map(
 		|signature| {
-			let arg_types = signature.arguments.iter().map(|&(_, ref ty)| quote! { #ty });
+			let (arg_pops, args) = arg_pops_toks(signature);
 			let check_value_if_payable = check_value_if_payable_toks(signature.is_payable);
 			quote! {
 				#check_value_if_payable
-				let mut stream = pwasm_abi::eth::Stream::new(payload);
+				let mut stream = pwasm_abi::eth::Stream::#stream_new(payload);
+				#arg_pops
+				#stream_finish
 				self.inner.constructor(
-					#(stream.pop::<#arg_types>().expect("argument decoding failed")),*
+					#(#args),*
 				);
 			}
 		}
@@ -369,32 +826,82 @@ fn generate_eth_endpoint(endpoint_name: &str, intf: &items::Interface) -> proc_m
 				let hash_literal = syn::Lit::Int(
 					syn::LitInt::new(signature.hash as u64, syn::IntSuffix::U32, Span::call_site()));
 				let ident = &signature.name;
-				let arg_types = signature.arguments.iter().map(|&(_, ref ty)| quote! { #ty });
+				let (arg_pops, args) = arg_pops_toks(signature);
 				let check_value_if_payable = check_value_if_payable_toks(signature.is_payable);
+				let unwrap_result = signature.revert.as_ref().map(|revert| {
+					let revert_payload = match *revert {
+						items::Revert::Reason => quote! {
+							Ok(pwasm_abi::eth::encode_revert_reason(AsRef::<str>::as_ref(&error)))
+						},
+						items::Revert::Custom(ref custom_error) => {
+							let selector_literal = syn::Lit::Int(syn::LitInt::new(
+								custom_error.selector as u64, syn::IntSuffix::U32, Span::call_site()));
+							quote! { pwasm_abi::eth::encode_custom_error(#selector_literal, &error) }
+						},
+					};
+					quote! {
+						let result = match result {
+							Ok(result) => result,
+							Err(error) => return Err(match #revert_payload {
+								Ok(payload) => pwasm_abi::eth::DispatchError::Revert(payload),
+								Err(error) => pwasm_abi::eth::DispatchError::Encode(error),
+							}),
+						};
+					}
+				});
 				if !signature.return_types.is_empty() {
-					let return_count_literal = syn::Lit::Int(
-						syn::LitInt::new(signature.return_types.len() as u64, syn::IntSuffix::Usize, Span::call_site()));
+					let return_types = &signature.return_types;
+					// Multiple return values are pushed one by one instead of
+					// being encoded as a single (possibly dynamic) tuple.
+					let result_push = match signature.output {
+						Some(syn::Type::Tuple(_)) => {
+							let indices = (0..signature.return_types.len()).map(syn::Index::from);
+							quote! { #(sink.push_ref(&result.#indices);)* }
+						},
+						_ => quote! { sink.push_ref(&result); },
+					};
+					Some(quote! {
+						#hash_literal => {
+							#check_value_if_payable
+							let mut stream = pwasm_abi::eth::Stream::#stream_new(method_payload);
+							#arg_pops
+							#stream_finish
+							let result = inner.#ident(
+								#(#args),*
+							);
+							#unwrap_result
+							let mut sink = pwasm_abi::eth::Sink::with_head_size(
+								0 #(+ <#return_types as pwasm_abi::eth::AbiEncode>::head_size())*
+							);
+							#result_push
+							sink.finalize().map_err(pwasm_abi::eth::DispatchError::Encode)
+						}
+					})
+				} else if unwrap_result.is_some() {
 					Some(quote! {
 						#hash_literal => {
 							#check_value_if_payable
-							let mut stream = pwasm_abi::eth::Stream::new(method_payload);
+							let mut stream = pwasm_abi::eth::Stream::#stream_new(method_payload);
+							#arg_pops
+							#stream_finish
 							let result = inner.#ident(
-								#(stream.pop::<#arg_types>().expect("argument decoding failed")),*
+								#(#args),*
 							);
-							let mut sink = pwasm_abi::eth::Sink::new(#return_count_literal);
-							sink.push(result);
-							sink.finalize_panicking()
+							#unwrap_result
+							Ok(Vec::new())
 						}
 					})
 				} else {
 					Some(quote! {
 						#hash_literal => {
 							#check_value_if_payable
-							let mut stream = pwasm_abi::eth::Stream::new(method_payload);
+							let mut stream = pwasm_abi::eth::Stream::#stream_new(method_payload);
+							#arg_pops
+							#stream_finish
 							inner.#ident(
-								#(stream.pop::<#arg_types>().expect("argument decoding failed")),*
+								#(#args),*
 							);
-							Vec::new()
+							Ok(Vec::new())
 						}
 					})
 				}
@@ -434,10 +941,10 @@ fn generate_eth_endpoint(endpoint_name: &str, intf: &items::Interface) -> proc_m
 		impl<T: #name_ident> pwasm_abi::eth::EndpointInterface for #endpoint_ident<T> {
 			#[allow(unused_mut)]
 			#[allow(unused_variables)]
-			fn dispatch(&mut self, payload: &[u8]) -> Vec<u8> {
+			fn try_dispatch(&mut self, payload: &[u8]) -> Result<Vec<u8>, pwasm_abi::eth::DispatchError> {
 				let inner = &mut self.inner;
 				if payload.len() < 4 {
-					panic!("Invalid abi invoke");
+					return Err(pwasm_abi::eth::DispatchError::ShortPayload);
 				}
 				let method_id = ((payload[0] as u32) << 24)
 					+ ((payload[1] as u32) << 16)
@@ -448,14 +955,15 @@ fn generate_eth_endpoint(endpoint_name: &str, intf: &items::Interface) -> proc_m
 
 				match method_id {
 					#(#branches,)*
-					_ => panic!("Invalid method signature"),
+					_ => Err(pwasm_abi::eth::DispatchError::InvalidSelector),
 				}
 			}
 
 			#[allow(unused_variables)]
 			#[allow(unused_mut)]
-			fn dispatch_ctor(&mut self, payload: &[u8]) {
+			fn try_dispatch_ctor(&mut self, payload: &[u8]) -> Result<(), pwasm_abi::eth::DispatchError> {
 				#ctor_branch
+				Ok(())
 			}
 		}
 	}
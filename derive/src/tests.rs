@@ -0,0 +1,363 @@
+//! Tests of the macros, mainly of the diagnostics they report
+
+use proc_macro2::{TokenStream, TokenTree};
+use serde_json;
+use syn;
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+
+use {impl_eth_abi, items, json, tuple, utils};
+
+/// A `compile_error!` invokation produced by a macro.
+#[derive(Debug, PartialEq)]
+struct CompileError {
+	/// The reported message.
+	message: String,
+	/// The line (starting at 1) of the offending code.
+	line: usize,
+	/// The column (starting at 0) of the offending code.
+	column: usize,
+}
+
+impl CompileError {
+	fn new(message: &str, line: usize, column: usize) -> Self {
+		CompileError { message: message.to_owned(), line, column }
+	}
+}
+
+/// Returns the `compile_error!` invokations of the given macro output.
+fn compile_errors(output: TokenStream) -> Vec<CompileError> {
+	let tokens: Vec<TokenTree> = output.into_iter().collect();
+	tokens.windows(3).filter_map(|window| match window {
+		[TokenTree::Ident(ref ident), TokenTree::Punct(ref punct), TokenTree::Group(ref group)]
+			if ident == "compile_error" && punct.as_char() == '!' =>
+		{
+			let message = syn::parse2::<syn::LitStr>(group.stream()).expect("message is a string literal");
+			let start = ident.span().start();
+			Some(CompileError { message: message.value(), line: start.line, column: start.column })
+		},
+		_ => None,
+	}).collect()
+}
+
+/// Returns the compile errors of `#[eth_abi(args)]` applied to `input`.
+fn eth_abi_errors(args: &str, input: &str) -> Vec<CompileError> {
+	let (input, param_attributes) = utils::ParamAttributes::strip(input.parse().expect("input is valid tokens"));
+	let args = Punctuated::<syn::NestedMeta, Token![,]>::parse_terminated
+		.parse_str(args)
+		.expect("arguments are valid");
+	let input = syn::parse2(input).expect("input is an item");
+	match impl_eth_abi(args.into_iter().collect(), input, &param_attributes) {
+		Ok(output) => compile_errors(output),
+		Err(err) => compile_errors(err.to_compile_error()),
+	}
+}
+
+/// Returns the JSON abi of the interface declared by `input`.
+fn json_abi(input: &str) -> serde_json::Value {
+	let (input, param_attributes) = utils::ParamAttributes::strip(input.parse().expect("input is valid tokens"));
+	let intf = items::Interface::from_item(syn::parse2(input).expect("input is an item"), &param_attributes)
+		.unwrap_or_else(|_| panic!("input is a valid interface"));
+	serde_json::to_value(json::Abi::from(&intf)).expect("abi is serializable")
+}
+
+/// Returns the compile errors of `#[derive(AbiType)]` applied to `input`.
+fn derive_abi_type_errors(input: &str) -> Vec<CompileError> {
+	match tuple::Tuple::from_derive_input(syn::parse_str(input).expect("input is a type declaration")) {
+		Ok(_) => Vec::new(),
+		Err(err) => compile_errors(err.to_compile_error()),
+	}
+}
+
+#[test]
+fn not_a_trait() {
+	assert_eq!(
+		eth_abi_errors("Endpoint", "\nstruct Contract;"),
+		vec![CompileError::new("eth_abi can only be applied to trait declarations", 2, 0)],
+	);
+}
+
+#[test]
+fn malformatted_argument() {
+	assert_eq!(
+		eth_abi_errors("Endpoint, \"Client\"", "trait Contract {}"),
+		vec![CompileError::new("found non-identifier argument at index 1 passed to eth_abi", 1, 10)],
+	);
+}
+
+#[test]
+fn invalid_number_of_arguments() {
+	// `strict` only counts as an option following the names
+	assert!(eth_abi_errors("Endpoint, Client, strict", "trait Contract {}").is_empty());
+	assert!(eth_abi_errors("strict", "trait Contract {}").is_empty());
+	assert_eq!(
+		eth_abi_errors("Endpoint, Client, Other", "trait Contract {}"),
+		vec![CompileError::new(
+			"found 3 arguments passed to eth_abi but expected 1 or 2 names followed by an optional `strict`", 1, 0)],
+	);
+}
+
+#[test]
+fn constant_and_payable() {
+	assert_eq!(
+		eth_abi_errors("Endpoint", "
+trait Contract {
+	#[constant]
+	#[payable]
+	fn balance(&mut self) -> u64;
+}"),
+		vec![CompileError::new("method balance can't be constant and payable at the same time", 5, 4)],
+	);
+}
+
+#[test]
+fn address_paths() {
+	// Addresses are only recognized by name from the modules exporting them.
+	assert_eq!(
+		eth_abi_errors("Endpoint", "
+trait Contract {
+	fn pay(&mut self, a: Address, b: H160, c: pwasm_abi::types::Address, d: ethereum_types::H160);
+	fn refund(&mut self, to: wallet::Address);
+}"),
+		vec![CompileError::new("type is not supported by the abi", 4, 26)],
+	);
+}
+
+#[test]
+fn unknown_types() {
+	// Every unsupported type of a method is reported.
+	assert_eq!(
+		eth_abi_errors("Endpoint", "
+trait Contract {
+	fn place(&mut self, position: Position, amount: f64) -> Vec<Order>;
+}"),
+		vec![
+			CompileError::new(
+				"type Position is not supported by the abi (structs have to derive AbiType before being used)",
+				3, 31,
+			),
+			CompileError::new(
+				"type f64 is not supported by the abi (structs have to derive AbiType before being used)",
+				3, 49,
+			),
+			CompileError::new(
+				"type Order is not supported by the abi (structs have to derive AbiType before being used)",
+				3, 61,
+			),
+		],
+	);
+}
+
+#[test]
+fn errors_of_several_items() {
+	// Errors of all items of the interface are reported together.
+	assert_eq!(
+		eth_abi_errors("Endpoint", "
+trait Contract {
+	fn withdraw(&mut self, amount: U256) -> Result<U256, u32>;
+	#[event]
+	fn transfer(&mut self, indexed_a: U256, indexed_b: U256, indexed_c: U256, indexed_d: U256);
+	#[abi(selector = \"balance\")]
+	fn balance(&mut self) -> U256;
+}"),
+		vec![
+			CompileError::new("the error type of withdraw has to be a String or a struct deriving AbiType", 3, 54),
+			CompileError::new("event transfer has 4 indexed parameters, but at most 3 are allowed", 5, 75),
+			CompileError::new(
+				"unsupported option of the abi attribute, expected `name = \"solidityName\"` or `selector = 0x12345678`",
+				6, 7,
+			),
+		],
+	);
+}
+
+#[test]
+fn invalid_event_option() {
+	assert_eq!(
+		eth_abi_errors("Endpoint", "
+trait Contract {
+	#[event(indexed)]
+	fn transfer(&mut self, value: U256);
+}"),
+		vec![CompileError::new(
+			"event transfer has an unsupported option, expected `#[event]` or `#[event(anonymous)]`",
+			3, 9,
+		)],
+	);
+}
+
+#[test]
+fn indexed_method_parameter() {
+	assert_eq!(
+		eth_abi_errors("Endpoint", "
+trait Contract {
+	fn constructor(&mut self, #[indexed] owner: Address);
+	fn transfer(&mut self, to: Address, #[indexed] value: U256);
+	#[event]
+	fn transferred(&mut self, #[indexed] to: Address, value: U256);
+}"),
+		vec![
+			CompileError::new(
+				"attribute #[indexed] of parameter owner can't be given for method constructor, which is no event",
+				3, 27,
+			),
+			CompileError::new(
+				"attribute #[indexed] of parameter value can't be given for method transfer, which is no event",
+				4, 37,
+			),
+		],
+	);
+}
+
+#[test]
+fn derive_unnamed_fields() {
+	assert_eq!(
+		derive_abi_type_errors("\nstruct Position(Address, U256);"),
+		vec![CompileError::new("AbiType can be derived for structs with named fields only", 2, 7)],
+	);
+}
+
+#[test]
+fn derive_unsupported_fields() {
+	assert_eq!(
+		derive_abi_type_errors("
+struct Position {
+	owner: Address,
+	amount: (),
+	prices: &'static [u32],
+}"),
+		vec![
+			CompileError::new("type is not supported by the abi", 4, 9),
+			CompileError::new("only &[u8] and &str can be borrowed", 5, 9),
+		],
+	);
+}
+
+#[test]
+fn selector_collision() {
+	// `transferFrom(address,address,uint256)` and `gasprice_bit_ether(int128)` share the selector.
+	assert_eq!(
+		eth_abi_errors("Endpoint", "
+trait Contract {
+	#[abi(name = \"transferFrom\")]
+	fn transfer_from(&mut self, from: Address, to: Address, value: U256);
+	fn gasprice_bit_ether(&mut self, value: i128);
+}"),
+		vec![CompileError::new(
+			"selector 0x23b872dd of method gasprice_bit_ether (gasprice_bit_ether(int128)) \
+			collides with method transfer_from (transferFrom(address,address,uint256))",
+			5, 4,
+		)],
+	);
+}
+
+#[test]
+fn duplicate_abi_name() {
+	assert_eq!(
+		eth_abi_errors("Endpoint", "
+trait Contract {
+	#[abi(name = \"foo\")]
+	fn a(&mut self, x: U256);
+	#[abi(name = \"foo\")]
+	fn b(&mut self, x: U256);
+}"),
+		vec![CompileError::new(
+			"selector 0x2fbebd38 of method b (foo(uint256)) collides with method a (foo(uint256))",
+			6, 4,
+		)],
+	);
+}
+
+#[test]
+fn selector_mismatch() {
+	// A given selector has to match the signature of the method.
+	assert_eq!(
+		eth_abi_errors("Endpoint", "
+trait Contract {
+	#[abi(name = \"balanceOf\", selector = 0x70a08231)]
+	fn balance_of(&mut self, owner: Address) -> U256;
+	#[abi(selector = 0x01ffc9a7)]
+	fn supports(&mut self, id: u32) -> bool;
+}"),
+		vec![CompileError::new(
+			"selector 0x01ffc9a7 of method supports differs from 0x9b37db9d of its signature supports(uint32), \
+			which can be renamed by `name = \"solidityName\"`",
+			5, 7,
+		)],
+	);
+}
+
+#[test]
+fn standard_method_declared() {
+	assert_eq!(
+		eth_abi_errors("Endpoint", "
+trait SupportsInterfaceContract {
+	#[constant]
+	#[abi(name = \"supportsInterface\")]
+	fn supports_interface(&mut self, interface_id: [u8; 4]) -> bool;
+}"),
+		Vec::new(),
+	);
+	// The standard method is declared only once.
+	assert_eq!(
+		eth_abi_errors("Endpoint", "
+trait Contract {
+	#[abi(name = \"supportsInterface\")]
+	fn supports_interface(&mut self, interface_id: [u8; 4]) -> bool;
+	#[abi(name = \"supportsInterface\")]
+	fn supports(&mut self, interface_id: [u8; 4]) -> bool;
+}"),
+		vec![CompileError::new(
+			"selector 0x01ffc9a7 of method supports (supportsInterface(bytes4)) \
+			collides with method supports_interface (supportsInterface(bytes4))",
+			6, 4,
+		)],
+	);
+}
+
+#[test]
+fn derive_conflicting_struct() {
+	assert_eq!(derive_abi_type_errors("struct Position { owner: Address, amount: U256 }"), Vec::new());
+	// Deriving for the same struct again is fine.
+	assert_eq!(derive_abi_type_errors("struct Position { owner: Address, amount: U256 }"), Vec::new());
+	assert_eq!(
+		derive_abi_type_errors("\nstruct Position { amount: U256 }"),
+		vec![CompileError::new(
+			"AbiType is already derived for another struct Position with other fields, \
+			structs deriving AbiType have to be named uniquely",
+			2, 7,
+		)],
+	);
+	// The struct known to interfaces is the one registered first.
+	assert_eq!(tuple::lookup("Position").map(|components| components.len()), Some(2));
+}
+
+#[test]
+fn json_event_inputs_in_declaration_order() {
+	let abi = json_abi("
+trait Contract {
+	#[event]
+	fn transfer(&mut self, value: U256, #[indexed] from: Address, indexed_to: Address);
+}");
+	let inputs: Vec<(String, bool)> = abi[0]["inputs"]
+		.as_array()
+		.expect("event has inputs")
+		.iter()
+		.map(|input| (input["name"].as_str().unwrap().to_owned(), input["indexed"].as_bool().unwrap()))
+		.collect();
+	assert_eq!(inputs, vec![
+		("value".to_owned(), false),
+		("from".to_owned(), true),
+		("indexed_to".to_owned(), true),
+	]);
+}
+
+#[test]
+fn snake_case() {
+	assert_eq!(utils::to_snake_case("TokenContract"), "token_contract");
+	assert_eq!(utils::to_snake_case("ERC20"), "erc20");
+	assert_eq!(utils::to_snake_case("ERC20Events"), "erc20_events");
+	assert_eq!(utils::to_snake_case("HTTPServer"), "http_server");
+	assert_eq!(utils::to_snake_case("Token_Contract"), "token_contract");
+	assert_eq!(utils::to_snake_case("contract"), "contract");
+}
@@ -0,0 +1,195 @@
+//! Structs encoded as Solidity ABI v2 tuples
+
+use {quote, syn, utils};
+use error::{self, Error, Result};
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use quote::TokenStreamExt;
+use proc_macro2;
+use syn::spanned::Spanned;
+
+/// A member of a tuple type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Component {
+	/// The name of the member, empty for members of Rust tuples.
+	pub name: String,
+	/// The canonicalized type of the member.
+	pub canonical: String,
+	/// The members of the member type if it is a tuple (or an array of tuples).
+	pub components: Vec<Component>,
+}
+
+thread_local! {
+	/// Components of the structs that derive `AbiType` by the struct name.
+	///
+	/// # Note
+	///
+	/// The registry only lives during the compilation of a single crate,
+	/// so a struct has to be declared before the interface using it.
+	/// See the documentation of the `AbiType` derive for its limits.
+	static TUPLES: RefCell<HashMap<String, Vec<Component>>> = RefCell::new(HashMap::new());
+}
+
+/// Returns the components of the struct with the given name
+/// if it derives `AbiType`.
+pub fn lookup(name: &str) -> Option<Vec<Component>> {
+	TUPLES.with(|tuples| tuples.borrow().get(name).cloned())
+}
+
+/// Makes the components of the struct `name` known to the canonicalization.
+///
+/// Fails if a struct with the same name but other components is already registered,
+/// since interfaces can't tell them apart.
+fn register(name: &syn::Ident, components: Vec<Component>) -> Result<()> {
+	TUPLES.with(|tuples| {
+		let mut tuples = tuples.borrow_mut();
+		if tuples.get(&name.to_string()).map_or(false, |registered| *registered != components) {
+			return Err(Error::conflicting_struct(name.span(), name));
+		}
+		tuples.insert(name.to_string(), components);
+		Ok(())
+	})
+}
+
+/// A named-field struct deriving `AbiType`.
+pub struct Tuple {
+	/// The name of the struct.
+	name: syn::Ident,
+	/// The named fields of the struct.
+	fields: Vec<(syn::Ident, syn::Type)>,
+}
+
+impl Tuple {
+	/// Creates the tuple representation of the given derive input
+	/// and makes its components known to the canonicalization.
+	///
+	/// Fails with the errors of all fields of unsupported types,
+	/// or if another struct with the same name has other fields.
+	pub fn from_derive_input(input: syn::DeriveInput) -> Result<Self> {
+		if !input.generics.params.is_empty() {
+			return Err(Error::generic_struct(input.generics.span(), &input.ident));
+		}
+		let fields = match input.data {
+			syn::Data::Struct(syn::DataStruct { fields: syn::Fields::Named(fields), .. }) => {
+				fields.named
+					.into_iter()
+					.map(|field| (field.ident.expect("named fields have identifiers"), field.ty))
+					.collect::<Vec<_>>()
+			},
+			_ => return Err(Error::unnamed_fields(input.ident.span())),
+		};
+		if fields.is_empty() {
+			return Err(Error::empty_struct(input.ident.span(), &input.ident));
+		}
+
+		let canonicals = error::collect(fields.iter().map(|&(_, ref ty)| utils::canonicalize_type(ty)))?;
+		register(
+			&input.ident,
+			fields.iter()
+				.zip(canonicals)
+				.map(|(&(ref ident, ref ty), canonical)| Component {
+					name: ident.to_string(),
+					canonical: canonical,
+					components: utils::components(ty),
+				})
+				.collect(),
+		)?;
+
+		Ok(Tuple {
+			name: input.ident,
+			fields: fields,
+		})
+	}
+}
+
+impl quote::ToTokens for Tuple {
+	fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+		let name = &self.name;
+		let canonical = format!(
+			"({})",
+			self.fields.iter().map(|&(_, ref ty)| utils::canonical_type(ty)).collect::<Vec<_>>().join(","),
+		);
+		// Repetitions of `quote!` consume what they iterate over,
+		// so the fields are repeated by reference to the collected vectors.
+		let field_names: &Vec<&syn::Ident> = &self.fields.iter().map(|&(ref ident, _)| ident).collect();
+		let field_types: &Vec<&syn::Type> = &self.fields.iter().map(|&(_, ref ty)| ty).collect();
+		let field_labels = self.fields.iter().map(|&(ref ident, _)| ident.to_string());
+
+		tokens.append_all(
+			quote! {
+				impl<'a> ::pwasm_abi::eth::AbiDecode<'a> for #name {
+					fn decode(stream: &mut ::pwasm_abi::eth::Stream<'a>) -> Result<Self, ::pwasm_abi::eth::Error> {
+						Ok(#name {
+							#(#field_names: stream.pop::<#field_types>().map_err(|error| error.at_field(#field_labels))?),*
+						})
+					}
+
+					const IS_FIXED: bool = true #(& <#field_types as ::pwasm_abi::eth::AbiDecode<'a>>::IS_FIXED)*;
+
+					::pwasm_abi::__with_alloc! {
+						fn abi_type() -> ::pwasm_abi::types::String {
+							#canonical.into()
+						}
+					}
+				}
+
+				impl ::pwasm_abi::eth::AbiEncode for #name {
+					fn encode(&self, sink: &mut ::pwasm_abi::eth::Sink) {
+						if <Self as ::pwasm_abi::eth::AbiEncode>::IS_FIXED {
+							#(sink.push_ref(&self.#field_names);)*
+						} else {
+							sink.frame(0 #(+ <#field_types as ::pwasm_abi::eth::AbiEncode>::head_size())*, |sink| {
+								#(sink.push_ref(&self.#field_names);)*
+							});
+						}
+					}
+
+					const IS_FIXED: bool = true #(& <#field_types as ::pwasm_abi::eth::AbiEncode>::IS_FIXED)*;
+
+					fn head_size() -> usize {
+						if <Self as ::pwasm_abi::eth::AbiEncode>::IS_FIXED {
+							0 #(+ <#field_types as ::pwasm_abi::eth::AbiEncode>::head_size())*
+						} else {
+							32
+						}
+					}
+				}
+
+				impl<'a> ::pwasm_abi::eth::AbiEncode for &'a #name {
+					fn encode(&self, sink: &mut ::pwasm_abi::eth::Sink) {
+						::pwasm_abi::eth::AbiEncode::encode(*self, sink)
+					}
+
+					const IS_FIXED: bool = <#name as ::pwasm_abi::eth::AbiEncode>::IS_FIXED;
+
+					fn head_size() -> usize {
+						<#name as ::pwasm_abi::eth::AbiEncode>::head_size()
+					}
+				}
+
+				::pwasm_abi::__with_alloc! {
+					impl ::pwasm_abi::eth::AbiEncodePacked for #name {
+						fn encode_packed(&self, out: &mut ::pwasm_abi::types::Vec<u8>) {
+							#(::pwasm_abi::eth::AbiEncodePacked::encode_packed(&self.#field_names, out);)*
+						}
+
+						fn encode_packed_element(&self, out: &mut ::pwasm_abi::types::Vec<u8>) {
+							#(::pwasm_abi::eth::AbiEncodePacked::encode_packed_element(&self.#field_names, out);)*
+						}
+					}
+
+					// Indexed structs are stored as the hash of their members, each padded to 32 bytes.
+					impl ::pwasm_abi::eth::AsLog for #name {
+						fn as_log(&self) -> ::pwasm_abi::types::H256 {
+							let mut encoded = ::pwasm_abi::types::Vec::new();
+							::pwasm_abi::eth::AbiEncodePacked::encode_packed_element(self, &mut encoded);
+							::pwasm_abi::eth::keccak(&encoded)
+						}
+					}
+				}
+			}
+		);
+	}
+}
@@ -1,4 +1,7 @@
-use {syn, quote};
+use {syn, quote, tuple};
+use error::{self, Error, Result};
+use proc_macro2::{Delimiter, Group, Span, TokenStream, TokenTree};
+use syn::spanned::Spanned;
 use tiny_keccak::Keccak;
 use byteorder::{BigEndian, ByteOrder};
 
@@ -30,6 +33,139 @@ impl<'a> Iterator for SignatureIterator<'a> {
 	}
 }
 
+/// Attributes accepted on the parameters of trait methods.
+const PARAM_ATTRIBUTES: &[&str] = &["indexed"];
+
+/// Attributes of the parameters of the trait methods, e.g. `#[indexed]`.
+///
+/// # Note
+///
+/// Attributes on parameters can't be parsed by `syn`, so they are
+/// taken out of the token stream before the trait is parsed.
+#[derive(Default)]
+pub struct ParamAttributes {
+	/// The method name, parameter name, attribute name and location of every attribute.
+	attributes: Vec<(String, String, String, Span)>,
+}
+
+impl ParamAttributes {
+	/// Removes the attributes of the method parameters from `input` and records them.
+	pub fn strip(input: TokenStream) -> (TokenStream, ParamAttributes) {
+		let mut param_attributes = ParamAttributes::default();
+		let output = param_attributes.strip_stream(input);
+		(output, param_attributes)
+	}
+
+	/// Returns `true` if the parameter `pat` of `method` has the attribute `name`.
+	pub fn has(&self, method: &syn::Ident, pat: &syn::Pat, name: &str) -> bool {
+		let param = match *pat {
+			syn::Pat::Ident(ref pat_ident) => pat_ident.ident.to_string(),
+			_ => return false,
+		};
+		self.attributes.iter().any(|&(ref attr_method, ref attr_param, ref attr_name, _)| {
+			*method == attr_method && *attr_param == param && attr_name == name
+		})
+	}
+
+	/// Checks that only parameters of the given `events` have attributes,
+	/// since `#[indexed]` applies to events only.
+	///
+	/// Fails with an error for every attribute of a parameter of another method.
+	pub fn check_events(&self, events: &[syn::Ident]) -> Result<()> {
+		error::collect(self.attributes.iter().map(|&(ref method, ref param, ref name, span)| {
+			if events.iter().any(|event| event == method) {
+				Ok(())
+			} else {
+				Err(Error::misplaced_param_attribute(span, name, param, method))
+			}
+		}))?;
+		Ok(())
+	}
+
+	fn strip_stream(&mut self, input: TokenStream) -> TokenStream {
+		// Name of the method whose parameters are the next parenthesized group.
+		let mut method: Option<String> = None;
+		let mut after_fn = false;
+		let mut output = Vec::new();
+		for token in input {
+			let token = match token {
+				TokenTree::Ident(ident) => {
+					if after_fn {
+						method = Some(ident.to_string());
+					}
+					after_fn = ident == "fn";
+					TokenTree::Ident(ident)
+				},
+				TokenTree::Group(group) => {
+					after_fn = false;
+					let stream = match (group.delimiter(), method.take()) {
+						(Delimiter::Parenthesis, Some(method)) => self.strip_params(&method, group.stream()),
+						_ => self.strip_stream(group.stream()),
+					};
+					let mut stripped = Group::new(group.delimiter(), stream);
+					stripped.set_span(group.span());
+					TokenTree::Group(stripped)
+				},
+				token => {
+					after_fn = false;
+					token
+				},
+			};
+			output.push(token);
+		}
+		output.into_iter().collect()
+	}
+
+	fn strip_params(&mut self, method: &str, input: TokenStream) -> TokenStream {
+		// Attributes preceding the next parameter.
+		let mut pending = Vec::new();
+		let mut output = Vec::new();
+		let mut tokens = input.into_iter().peekable();
+		while let Some(token) = tokens.next() {
+			match token {
+				TokenTree::Punct(punct) => {
+					let name = match tokens.peek() {
+						Some(&TokenTree::Group(ref group))
+							if punct.as_char() == '#' && group.delimiter() == Delimiter::Bracket =>
+						{
+							param_attribute_name(group.stream())
+						},
+						_ => None,
+					};
+					match name {
+						Some(name) => {
+							tokens.next();
+							pending.push((name, punct.span()));
+						},
+						None => output.push(TokenTree::Punct(punct)),
+					}
+				},
+				TokenTree::Ident(ident) => {
+					if ident != "mut" && ident != "ref" {
+						for (name, span) in pending.drain(..) {
+							self.attributes.push((method.to_owned(), ident.to_string(), name, span));
+						}
+					}
+					output.push(TokenTree::Ident(ident));
+				},
+				token => output.push(token),
+			}
+		}
+		output.into_iter().collect()
+	}
+}
+
+/// Returns the name of the attribute with the content `stream` if it is a parameter attribute.
+fn param_attribute_name(stream: TokenStream) -> Option<String> {
+	let tokens: Vec<TokenTree> = stream.into_iter().collect();
+	match tokens.as_slice() {
+		[TokenTree::Ident(ref ident)] if PARAM_ATTRIBUTES.iter().any(|name| ident == name) => {
+			Some(ident.to_string())
+		},
+		_ => None,
+	}
+}
+
 pub fn iter_signature(method_sig: &syn::MethodSig) -> SignatureIterator {
 	SignatureIterator {
 		method_sig: method_sig,
@@ -68,88 +204,274 @@ pub fn produce_signature<T: quote::ToTokens>(
 	}
 }
 
-fn push_int_const_expr(target: &mut String, expr: &syn::Expr) {
+fn push_int_const_expr(target: &mut String, expr: &syn::Expr) -> Result<()> {
 	match expr {
 		syn::Expr::Lit(syn::ExprLit{lit: syn::Lit::Int(lit_int), ..}) => {
-			target.push_str(&format!("{}", lit_int.value()))
+			target.push_str(&format!("{}", lit_int.value()));
+			Ok(())
 		}
-		_ => panic!("Cannot use something other than integer literal in this constant expression"),
+		_ => Err(Error::invalid_array_length(expr.span())),
 	}
 }
 
-fn push_canonicalized_vec(target: &mut String, args: &syn::PathArguments) {
-	match args {
-		syn::PathArguments::AngleBracketed(gen_args) => {
-			let last_arg = gen_args.args.last().unwrap();
-			let last_type = last_arg.value();
-			if let syn::GenericArgument::Type(syn::Type::Path(type_path)) = last_type {
-				return if type_path.qself.is_none()
-					&& type_path.path.segments.last().unwrap().value().ident == "u8"
-				{
-					target.push_str("bytes");
-				}
-				else {
-					push_canonicalized_path(target, type_path);
-					target.push_str("[]");
-				}
+fn push_canonicalized_vec(target: &mut String, seg: &syn::PathSegment) -> Result<()> {
+	if let syn::PathArguments::AngleBracketed(ref gen_args) = seg.arguments {
+		if let Some(syn::GenericArgument::Type(elem_type)) = gen_args.args.last().map(|arg| arg.into_value()) {
+			if is_u8_type(elem_type) {
+				target.push_str("bytes");
+				return Ok(());
 			}
-			panic!("Unsupported generic arguments")
-		},
-		_ => panic!("Unsupported vec arguments"),
+			push_canonicalized_type(target, elem_type)?;
+			target.push_str("[]");
+			return Ok(());
+		}
 	}
+	Err(Error::unsupported_type(seg.span()))
 }
 
-fn push_canonicalized_primitive(target: &mut String, seg: &syn::PathSegment) {
+fn push_canonicalized_array_iter(target: &mut String, seg: &syn::PathSegment) -> Result<()> {
+	if let syn::PathArguments::AngleBracketed(ref gen_args) = seg.arguments {
+		if let Some(syn::GenericArgument::Type(elem_type)) = gen_args.args.last().map(|arg| arg.into_value()) {
+			// Elements are decoded one by one, so `ArrayIter<u8>` is `uint8[]`
+			push_canonicalized_type(target, elem_type)?;
+			target.push_str("[]");
+			return Ok(());
+		}
+	}
+	Err(Error::unsupported_type(seg.span()))
+}
+
+fn push_canonicalized_primitive(target: &mut String, seg: &syn::PathSegment) -> Result<()> {
 	match seg.ident.to_string().as_str() {
+		"u8" => target.push_str("uint8"),
+		"i8" => target.push_str("int8"),
+		"u16" => target.push_str("uint16"),
+		"i16" => target.push_str("int16"),
 		"u32" => target.push_str("uint32"),
 		"i32" => target.push_str("int32"),
 		"u64" => target.push_str("uint64"),
 		"i64" => target.push_str("int64"),
+		"u128" => target.push_str("uint128"),
+		"i128" => target.push_str("int128"),
 		"U256" => target.push_str("uint256"),
-		"H256" => target.push_str("uint256"),
-		"Address" => target.push_str("address"),
+		"I256" => target.push_str("int256"),
+		// Contracts deployed with the former mapping of `H256` keep their
+		// selectors by enabling the `legacy-h256` feature.
+		"H256" if cfg!(feature = "legacy-h256") => target.push_str("uint256"),
+		"H256" => target.push_str("bytes32"),
+		"H160" | "Address" => target.push_str("address"),
 		"String" => target.push_str("string"),
 		"bool" => target.push_str("bool"),
-		"Vec" => push_canonicalized_vec(target, &seg.arguments),
-		val => panic!(
-			"[e1] Unable to handle param of type {}: not supported by abi",
-			val
-		),
+		"Vec" => return push_canonicalized_vec(target, seg),
+		"ArrayIter" => return push_canonicalized_array_iter(target, seg),
+		val => match tuple::lookup(val) {
+			Some(components) => push_canonicalized_components(target, &components),
+			None => return Err(Error::unknown_type(seg.span(), val)),
+		},
 	}
+	Ok(())
 }
 
-fn push_canonicalized_path(target: &mut String, type_path: &syn::TypePath) {
-	assert!(type_path.qself.is_none(), "Unsupported type path for canonicalization!");
-	let last_path = type_path.path.segments.last().unwrap();
-	push_canonicalized_primitive(target, *last_path.value())
+fn push_canonicalized_components(target: &mut String, components: &[tuple::Component]) {
+	target.push('(');
+	for (i, component) in components.iter().enumerate() {
+		if i != 0 { target.push(','); }
+		target.push_str(&component.canonical);
+	}
+	target.push(')');
 }
 
-fn push_canonicalized_type(target: &mut String, ty: &syn::Type) {
+/// Modules exporting the address type, which is only recognized by name
+/// when it is used unqualified or from one of them.
+const ADDRESS_MODULES: &[&[&str]] = &[
+	&["pwasm_abi", "types"],
+	&["pwasm_std", "types"],
+	&["ethereum_types"],
+];
+
+fn is_address_path(path: &syn::Path) -> bool {
+	let modules: Vec<String> = path.segments.iter()
+		.take(path.segments.len() - 1)
+		.map(|seg| seg.ident.to_string())
+		.collect();
+	modules.is_empty() || ADDRESS_MODULES.iter().any(|module| *module == &modules[..])
+}
+
+fn push_canonicalized_path(target: &mut String, type_path: &syn::TypePath) -> Result<()> {
+	match type_path.path.segments.last() {
+		Some(last_path) => {
+			let ident = &last_path.value().ident;
+			if (ident == "H160" || ident == "Address") && !is_address_path(&type_path.path) {
+				return Err(Error::unsupported_type(type_path.span()));
+			}
+			push_canonicalized_primitive(target, *last_path.value())
+		},
+		None => Err(Error::unsupported_type(type_path.span())),
+	}
+}
+
+fn push_canonicalized_type(target: &mut String, ty: &syn::Type) -> Result<()> {
 	match ty {
 		syn::Type::Path(type_path) if type_path.qself.is_none() => {
 			push_canonicalized_path(target, &type_path)
 		},
 		syn::Type::Array(type_array) => {
 			// Special cases for `bytesN`
-			if let syn::Type::Path(type_path) = &*type_array.elem {
-				if "u8" == type_path.path.segments.last().unwrap().value().ident.to_string() {
-					target.push_str("bytes");
-					push_int_const_expr(target, &type_array.len);
-					return;
-				}
+			if is_u8_type(&type_array.elem) {
+				target.push_str("bytes");
+				return push_int_const_expr(target, &type_array.len);
 			}
 
-			panic!("Unsupported! Use variable-size arrays")
+			push_canonicalized_type(target, &type_array.elem)?;
+			target.push('[');
+			push_int_const_expr(target, &type_array.len)?;
+			target.push(']');
+			Ok(())
 		},
-		other_type => panic!("[e2] Unable to handle param of type {:?}: not supported by abi", other_type),
+		syn::Type::Tuple(type_tuple) if !type_tuple.elems.is_empty() => {
+			let elems = error::collect(type_tuple.elems.iter().map(canonicalize_type))?;
+			target.push('(');
+			target.push_str(&elems.join(","));
+			target.push(')');
+			Ok(())
+		},
+		// Borrowed `&[u8]` and `&str` are decoded without copying the payload
+		syn::Type::Reference(type_ref) if type_ref.mutability.is_none() => {
+			match *type_ref.elem {
+				syn::Type::Slice(ref type_slice) if is_u8_type(&type_slice.elem) => target.push_str("bytes"),
+				syn::Type::Path(ref type_path) if type_path.path.is_ident("str") => target.push_str("string"),
+				_ => return Err(Error::unsupported_borrow(ty.span())),
+			}
+			Ok(())
+		},
+		_ => Err(Error::unsupported_type(ty.span())),
+	}
+}
+
+fn is_u8_type(ty: &syn::Type) -> bool {
+	match ty {
+		syn::Type::Path(type_path) => type_path.qself.is_none() && type_path.path.is_ident("u8"),
+		_ => false,
 	}
 }
 
-/// Returns the canonicalized string representation for the given type.
-pub fn canonicalize_type(ty: &syn::Type) -> String {
+/// Returns the canonicalized string representation for the given type,
+/// or an error located at the part of the type unsupported by the abi.
+pub fn canonicalize_type(ty: &syn::Type) -> Result<String> {
 	let mut result = String::new();
-	push_canonicalized_type(&mut result, ty);
-	result
+	push_canonicalized_type(&mut result, ty)?;
+	Ok(result)
+}
+
+/// Returns the canonicalized string representation for the given type
+/// that was already validated by `canonicalize_type`.
+pub fn canonical_type(ty: &syn::Type) -> String {
+	canonicalize_type(ty).expect("types are validated before code generation")
+}
+
+/// Returns the tuple components of the given type.
+///
+/// # Note
+///
+/// For arrays these are the components of the element type.
+/// The result is empty if the type is no tuple (or array of tuples).
+pub fn components(ty: &syn::Type) -> Vec<tuple::Component> {
+	match ty {
+		syn::Type::Tuple(type_tuple) => {
+			type_tuple.elems
+				.iter()
+				.map(|elem| tuple::Component {
+					name: String::new(),
+					canonical: canonical_type(elem),
+					components: components(elem),
+				})
+				.collect()
+		},
+		syn::Type::Array(type_array) => components(&type_array.elem),
+		syn::Type::Path(type_path) if type_path.qself.is_none() => {
+			let last_seg = type_path.path.segments.last().unwrap();
+			let seg = last_seg.value();
+			match (seg.ident.to_string().as_str(), &seg.arguments) {
+				("Vec", syn::PathArguments::AngleBracketed(gen_args))
+				| ("ArrayIter", syn::PathArguments::AngleBracketed(gen_args)) => {
+					match gen_args.args.last().map(|arg| arg.into_value()) {
+						Some(syn::GenericArgument::Type(elem)) => components(elem),
+						_ => Vec::new(),
+					}
+				},
+				(name, _) => tuple::lookup(name).unwrap_or_default(),
+			}
+		},
+		_ => Vec::new(),
+	}
+}
+
+/// Returns the types `T` and `E` if the given type is a `Result<T, E>`.
+pub fn result_types(ty: &syn::Type) -> Option<(syn::Type, syn::Type)> {
+	let type_path = match ty {
+		syn::Type::Path(type_path) if type_path.qself.is_none() => type_path,
+		_ => return None,
+	};
+	let last_seg = type_path.path.segments.last()?;
+	let seg = last_seg.value();
+	if seg.ident != "Result" {
+		return None;
+	}
+	let gen_args = match seg.arguments {
+		syn::PathArguments::AngleBracketed(ref gen_args) => gen_args,
+		_ => return None,
+	};
+	let mut types = gen_args.args.iter().filter_map(|arg| match arg {
+		syn::GenericArgument::Type(ty) => Some(ty.clone()),
+		_ => None,
+	});
+	match (types.next(), types.next(), types.next()) {
+		(Some(ok_type), Some(err_type), None) => Some((ok_type, err_type)),
+		_ => None,
+	}
+}
+
+/// Returns `true` if the given type is `String` or a `str` reference.
+pub fn is_string_type(ty: &syn::Type) -> bool {
+	match ty {
+		syn::Type::Path(type_path) => type_path.path.segments
+			.last()
+			.map_or(false, |seg| seg.value().ident == "String"),
+		syn::Type::Reference(type_ref) => match *type_ref.elem {
+			syn::Type::Path(ref type_path) => type_path.path.is_ident("str"),
+			_ => false,
+		},
+		_ => false,
+	}
+}
+
+/// Returns the type of the given canonicalized type as used by the JSON abi,
+/// e.g. `tuple[]` for `(uint256,bool)[]`.
+pub fn json_type(canonical: &str) -> String {
+	if !canonical.starts_with('(') {
+		return canonical.to_owned();
+	}
+	let mut depth = 0;
+	for (i, c) in canonical.char_indices() {
+		match c {
+			'(' => depth += 1,
+			')' => {
+				depth -= 1;
+				if depth == 0 {
+					return format!("tuple{}", &canonical[i + 1..]);
+				}
+			},
+			_ => {},
+		}
+	}
+	canonical.to_owned()
+}
+
+/// Returns `true` if indexed event parameters of the given canonicalized type
+/// are stored in the topic as the Keccak hash of their value, which is the
+/// case for all but value types like `uint256`, `address` or `bytes32`.
+pub fn is_hashed_in_topic(canonical: &str) -> bool {
+	canonical.contains('[') || canonical.starts_with('(') || canonical == "bytes" || canonical == "string"
 }
 
 /// Returns the canonicalized string representation for the function
@@ -159,17 +481,47 @@ pub fn canonicalize_type(ty: &syn::Type) -> String {
 /// 
 /// The result can be used by `function_selector` in order to retrieve
 /// the function selector for the associated function.
-pub fn canonicalize_fn(name: &syn::Ident, method_sig: &syn::MethodSig) -> String {
-	let mut s = String::new();
-	s.push_str(&name.to_string());
-	s.push('(');
-	let total_len = method_sig.decl.inputs.len();
-	for (i, (_, ty)) in iter_signature(method_sig).enumerate() {
-		push_canonicalized_type(&mut s, &ty);
-		if i != total_len-2 { s.push(','); }
-	}
-	s.push(')');
-	s
+///
+/// Fails with the errors of all parameters of unsupported types.
+pub fn canonicalize_fn(name: &str, method_sig: &syn::MethodSig) -> Result<String> {
+	let params = error::collect(iter_signature(method_sig).map(|(_, ty)| canonicalize_type(&ty)))?;
+	Ok(format!("{}({})", name, params.join(",")))
+}
+
+/// Returns the given snake case identifier in camel case, e.g. `BazFired` for `baz_fired`.
+pub fn to_camel_case(name: &str) -> String {
+	name.split('_')
+		.flat_map(|word| {
+			let mut chars = word.chars();
+			chars.next()
+				.into_iter()
+				.flat_map(char::to_uppercase)
+				.chain(chars)
+		})
+		.collect()
+}
+
+/// Returns the given camel case identifier in snake case, e.g. `token_contract` for `TokenContract`.
+///
+/// Runs of uppercase letters are one word, e.g. `erc20_events` for `ERC20Events`.
+pub fn to_snake_case(name: &str) -> String {
+	let chars: Vec<char> = name.chars().collect();
+	let mut result = String::new();
+	for (i, &c) in chars.iter().enumerate() {
+		if c.is_uppercase() {
+			// A word starts after a lowercase letter or digit, or with the
+			// last uppercase letter of a run followed by a lowercase letter.
+			let starts_word = i != 0 && (
+				!chars[i - 1].is_uppercase()
+					|| chars.get(i + 1).map_or(false, |next| next.is_lowercase())
+			);
+			if starts_word && chars[i - 1] != '_' { result.push('_'); }
+			result.extend(c.to_lowercase());
+		} else {
+			result.push(c);
+		}
+	}
+	result
 }
 
 /// Returns the Keccak hash (256-bits) of the given byte slice.
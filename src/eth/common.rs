@@ -1,18 +1,18 @@
 //! Common types encoding/decoding
 
 use lib::*;
-use super::{util, Stream, AbiType, Sink, Error};
-use super::types::{H160, H256, U256};
+use super::{util, Stream, AbiDecode, AbiEncode, Sink, Error, ErrorKind};
+use super::types::{H160, H256, I256, U256};
 use pwasm_std::str::from_utf8;
 
-impl AbiType for u32 {
-	fn decode(stream: &mut Stream) -> Result<Self, Error> {
+impl<'a> AbiDecode<'a> for u32 {
+	fn decode(stream: &mut Stream<'a>) -> Result<Self, Error> {
 		let previous_position = stream.advance(32)?;
 
 		let slice = &stream.payload()[previous_position..stream.position()];
 
 		if !slice[..28].iter().all(|x| *x == 0) {
-			return Err(Error::InvalidU32)
+			return Err(ErrorKind::InvalidU32.into())
 		}
 
 		let result = ((slice[28] as u32) << 24) +
@@ -23,21 +23,30 @@ impl AbiType for u32 {
 		Ok(result)
 	}
 
-	fn encode(self, sink: &mut Sink) {
-		sink.preamble_mut().extend_from_slice(&util::pad_u32(self)[..]);
+	const IS_FIXED: bool = true;
+
+	#[cfg(feature = "alloc")]
+	fn abi_type() -> String {
+		"uint32".into()
+	}
+}
+
+impl AbiEncode for u32 {
+	fn encode(&self, sink: &mut Sink) {
+		sink.write(&util::pad_u32(*self));
 	}
 
 	const IS_FIXED: bool = true;
 }
 
-impl AbiType for u64 {
-	fn decode(stream: &mut Stream) -> Result<Self, Error> {
+impl<'a> AbiDecode<'a> for u64 {
+	fn decode(stream: &mut Stream<'a>) -> Result<Self, Error> {
 		let previous_position = stream.advance(32)?;
 
 		let slice = &stream.payload()[previous_position..stream.position()];
 
 		if !slice[..24].iter().all(|x| *x == 0) {
-			return Err(Error::InvalidU64)
+			return Err(ErrorKind::InvalidU64.into())
 		}
 
 		let result =
@@ -53,83 +62,159 @@ impl AbiType for u64 {
 		Ok(result)
 	}
 
-	fn encode(self, sink: &mut Sink) {
-		sink.preamble_mut().extend_from_slice(&util::pad_u64(self)[..]);
+	const IS_FIXED: bool = true;
+
+	#[cfg(feature = "alloc")]
+	fn abi_type() -> String {
+		"uint64".into()
+	}
+}
+
+impl AbiEncode for u64 {
+	fn encode(&self, sink: &mut Sink) {
+		sink.write(&util::pad_u64(*self));
 	}
 
 	const IS_FIXED: bool = true;
 }
 
-impl AbiType for Vec<u8> {
-	fn decode(stream: &mut Stream) -> Result<Self, Error> {
-		let len = u32::decode(stream)? as usize;
+impl<'a> AbiDecode<'a> for u8 {
+	fn decode(stream: &mut Stream<'a>) -> Result<Self, Error> {
+		let previous_position = stream.advance(32)?;
+
+		let slice = &stream.payload()[previous_position..stream.position()];
 
-		let result = stream.payload()[stream.position()..stream.position() + len].to_vec();
-		stream.advance(len)?;
-		stream.finish_advance();
+		if !slice[..31].iter().all(|x| *x == 0) {
+			return Err(ErrorKind::InvalidU8.into())
+		}
 
-		Ok(result)
+		Ok(slice[31])
+	}
+
+	const IS_FIXED: bool = true;
+
+	#[cfg(feature = "alloc")]
+	fn abi_type() -> String {
+		"uint8".into()
+	}
+
+	// `Vec<u8>` is `bytes` rather than `uint8[]`
+	#[cfg(feature = "alloc")]
+	fn abi_vec_type() -> String {
+		"bytes".into()
+	}
+
+	#[cfg(feature = "alloc")]
+	fn decode_vec(stream: &mut Stream<'a>) -> Result<Vec<Self>, Error> {
+		let bytes = <&[u8]>::decode(stream)?;
+		stream.charge((bytes.len() + 31) / 32)?;
+		Ok(bytes.to_vec())
+	}
+
+	// `[u8; N]` is `bytesN` rather than `uint8[N]`
+	#[cfg(feature = "alloc")]
+	fn abi_array_type(len: usize) -> String {
+		format!("bytes{}", len)
 	}
 
-	fn encode(self, sink: &mut Sink) {
-		let mut val = self;
-		let len = val.len();
-		if len % 32 != 0 {
-			val.resize(len + (32 - len % 32), 0);
+	fn decode_array(stream: &mut Stream<'a>, elements: &mut [Option<Self>]) -> Result<(), Error> {
+		let slice = stream.read(32)?;
+		if stream.is_strict() && !slice[elements.len()..].iter().all(|x| *x == 0) {
+			return Err(ErrorKind::NonZeroPadding.into());
 		}
-		sink.push(len as u32);
-		sink.preamble_mut().extend_from_slice(&val[..]);
+		for (element, byte) in elements.iter_mut().zip(slice.iter()) {
+			*element = Some(*byte);
+		}
+		Ok(())
 	}
+}
 
-	const IS_FIXED: bool = false;
+impl AbiEncode for u8 {
+	fn encode(&self, sink: &mut Sink) {
+		sink.write(&util::pad_u32(*self as u32));
+	}
+
+	const IS_FIXED: bool = true;
+
+	// `[u8]` is `bytes` rather than `uint8[]`
+	fn encode_slice(elements: &[Self], sink: &mut Sink) {
+		sink.push(elements.len() as u32);
+		sink.write_padded(elements);
+	}
+
+	// `[u8; N]` is `bytesN` rather than `uint8[N]`
+	fn array_head_size(_len: usize) -> usize {
+		32
+	}
+
+	fn encode_array(elements: &[Self], sink: &mut Sink) {
+		let mut padded = [0u8; 32];
+		padded[0..elements.len()].copy_from_slice(elements);
+		sink.write(&padded);
+	}
 }
 
-impl AbiType for String {
-	fn decode(stream: &mut Stream) -> Result<Self, Error> {
-		let len = u32::decode(stream)? as usize;
+#[cfg(feature = "alloc")]
+impl<'a> AbiDecode<'a> for String {
+	fn decode(stream: &mut Stream<'a>) -> Result<Self, Error> {
+		let string = <&str>::decode(stream)?;
+		stream.charge((string.len() + 31) / 32)?;
+		Ok(string.to_string())
+	}
 
-		let result = from_utf8(&stream.payload()[stream.position()..stream.position() + len])
-			.map_err(|_err| Error::Other)?
-			.to_string();
+	const IS_FIXED: bool = false;
 
-		stream.advance(len)?;
-		stream.finish_advance();
+	fn abi_type() -> String {
+		"string".into()
+	}
+}
 
-		Ok(result)
+#[cfg(feature = "alloc")]
+impl AbiEncode for String {
+	fn encode(&self, sink: &mut Sink) {
+		self.as_str().encode(sink)
 	}
 
-	fn encode(self, sink: &mut Sink) {
-		let mut val = self.into_bytes();
-		let len = val.len();
-		if len % 32 != 0 {
-			val.resize(len + (32 - len % 32), 0);
-		}
-		sink.push(len as u32);
-		sink.preamble_mut().extend_from_slice(&val[..]);
+	const IS_FIXED: bool = false;
+}
+
+impl AbiEncode for str {
+	fn encode(&self, sink: &mut Sink) {
+		sink.push(self.len() as u32);
+		sink.write_padded(self.as_bytes());
 	}
 
 	const IS_FIXED: bool = false;
 }
 
-impl AbiType for bool {
-	fn decode(stream: &mut Stream) -> Result<Self, Error> {
+impl<'a> AbiDecode<'a> for bool {
+	fn decode(stream: &mut Stream<'a>) -> Result<Self, Error> {
 		let decoded = u32::decode(stream)?;
 		match decoded {
 			0 => Ok(false),
 			1 => Ok(true),
-			_ => Err(Error::InvalidBool),
+			_ => Err(ErrorKind::InvalidBool.into()),
 		}
 	}
 
-	fn encode(self, sink: &mut Sink) {
-		sink.preamble_mut().extend_from_slice(&util::pad_u32(match self { true => 1, false => 0})[..]);
+	const IS_FIXED: bool = true;
+
+	#[cfg(feature = "alloc")]
+	fn abi_type() -> String {
+		"bool".into()
+	}
+}
+
+impl AbiEncode for bool {
+	fn encode(&self, sink: &mut Sink) {
+		sink.write(&util::pad_u32(match *self { true => 1, false => 0}));
 	}
 
 	const IS_FIXED: bool = true;
 }
 
-impl AbiType for U256 {
-	fn decode(stream: &mut Stream) -> Result<Self, Error> {
+impl<'a> AbiDecode<'a> for U256 {
+	fn decode(stream: &mut Stream<'a>) -> Result<Self, Error> {
 		let previous = stream.advance(32)?;
 
 		Ok(
@@ -137,66 +222,105 @@ impl AbiType for U256 {
 		)
 	}
 
-	fn encode(self, sink: &mut Sink) {
-		let tail = sink.preamble_mut().len();
-		sink.preamble_mut().resize(tail + 32, 0);
-		self.to_big_endian(&mut sink.preamble_mut()[tail..tail+32]);
+	const IS_FIXED: bool = true;
+
+	#[cfg(feature = "alloc")]
+	fn abi_type() -> String {
+		"uint256".into()
+	}
+}
+
+impl AbiEncode for U256 {
+	fn encode(&self, sink: &mut Sink) {
+		let mut encoded = [0u8; 32];
+		self.to_big_endian(&mut encoded);
+		sink.write(&encoded);
 	}
 
 	const IS_FIXED: bool = true;
 }
 
-impl AbiType for H160 {
-	fn decode(stream: &mut Stream) -> Result<Self, Error> {
+impl<'a> AbiDecode<'a> for H160 {
+	fn decode(stream: &mut Stream<'a>) -> Result<Self, Error> {
 		let arr = <H256>::decode(stream)?;
+		if stream.is_strict() && !arr.as_ref()[..12].iter().all(|x| *x == 0) {
+			return Err(ErrorKind::NonZeroPadding.into());
+		}
 		Ok(H160::from(arr).into())
 	}
 
-	fn encode(self, sink: &mut Sink) {
-		H256::from(self).encode(sink)
+	const IS_FIXED: bool = true;
+
+	#[cfg(feature = "alloc")]
+	fn abi_type() -> String {
+		"address".into()
+	}
+}
+
+impl AbiEncode for H160 {
+	fn encode(&self, sink: &mut Sink) {
+		H256::from(*self).encode(sink)
 	}
 
 	const IS_FIXED: bool = true;
 }
 
-impl AbiType for H256 {
-	fn decode(stream: &mut Stream) -> Result<Self, Error> {
+impl<'a> AbiDecode<'a> for H256 {
+	fn decode(stream: &mut Stream<'a>) -> Result<Self, Error> {
 		let arr = <[u8; 32]>::decode(stream)?;
 		Ok(arr.into())
 	}
 
-	fn encode(self, sink: &mut Sink) {
+	const IS_FIXED: bool = true;
+
+	#[cfg(feature = "alloc")]
+	fn abi_type() -> String {
+		"bytes32".into()
+	}
+}
+
+impl AbiEncode for H256 {
+	fn encode(&self, sink: &mut Sink) {
 		self.as_fixed_bytes().encode(sink)
 	}
 
 	const IS_FIXED: bool = true;
 }
 
-impl<T: AbiType> AbiType for Vec<T> {
-	fn decode(stream: &mut Stream) -> Result<Self, Error> {
-		let len = u32::decode(stream)? as usize;
-		let mut result = Vec::with_capacity(len);
-		for _ in 0..len {
-			result.push(stream.pop()?);
-		}
-		Ok(result)
+#[cfg(feature = "alloc")]
+impl<'a, T: AbiDecode<'a>> AbiDecode<'a> for Vec<T> {
+	fn decode(stream: &mut Stream<'a>) -> Result<Self, Error> {
+		T::decode_vec(stream)
 	}
 
-	fn encode(self, sink: &mut Sink) {
-		sink.push(self.len() as u32);
+	const IS_FIXED: bool = false;
 
-		for member in self.into_iter() {
-			sink.push(member);
-		}
+	fn abi_type() -> String {
+		T::abi_vec_type()
+	}
+}
+
+#[cfg(feature = "alloc")]
+impl<T: AbiEncode> AbiEncode for Vec<T> {
+	fn encode(&self, sink: &mut Sink) {
+		T::encode_slice(self, sink)
 	}
 
 	const IS_FIXED: bool = false;
 }
 
-impl AbiType for i32 {
-	fn decode(stream: &mut Stream) -> Result<Self, Error> {
+impl<T: AbiEncode> AbiEncode for [T] {
+	fn encode(&self, sink: &mut Sink) {
+		T::encode_slice(self, sink)
+	}
 
-		let is_negative = stream.peek() & 0x80 != 0;
+	const IS_FIXED: bool = false;
+}
+
+impl<'a> AbiDecode<'a> for i32 {
+	fn decode(stream: &mut Stream<'a>) -> Result<Self, Error> {
+
+		let is_negative = stream.peek()? & 0x80 != 0;
 
 		if !is_negative {
 			return Ok(u32::decode(stream)? as i32);
@@ -208,7 +332,7 @@ impl AbiType for i32 {
 
 		// only negative path here
 		if !slice[0..28].iter().all(|x| *x == 0xff) {
-			return Err(Error::InvalidPadding);
+			return Err(ErrorKind::InvalidPadding.into());
 		}
 
 		let result = ((slice[28] as u32) << 24) +
@@ -219,17 +343,26 @@ impl AbiType for i32 {
 		Ok(result as i32)
 	}
 
-	fn encode(self, sink: &mut Sink) {
-		sink.preamble_mut().extend_from_slice(&util::pad_i32(self)[..]);
+	const IS_FIXED: bool = true;
+
+	#[cfg(feature = "alloc")]
+	fn abi_type() -> String {
+		"int32".into()
+	}
+}
+
+impl AbiEncode for i32 {
+	fn encode(&self, sink: &mut Sink) {
+		sink.write(&util::pad_i32(*self));
 	}
 
 	const IS_FIXED: bool = true;
 }
 
-impl AbiType for i64 {
-	fn decode(stream: &mut Stream) -> Result<Self, Error> {
+impl<'a> AbiDecode<'a> for i64 {
+	fn decode(stream: &mut Stream<'a>) -> Result<Self, Error> {
 
-		let is_negative = stream.peek() & 0x80 != 0;
+		let is_negative = stream.peek()? & 0x80 != 0;
 
 		if !is_negative {
 			return Ok(u64::decode(stream)? as i64);
@@ -241,7 +374,7 @@ impl AbiType for i64 {
 
 		// only negative path here
 		if !slice[0..24].iter().all(|x| *x == 0xff) {
-			return Err(Error::InvalidPadding);
+			return Err(ErrorKind::InvalidPadding.into());
 		}
 
 		let result =
@@ -257,28 +390,52 @@ impl AbiType for i64 {
 		Ok(result as i64)
 	}
 
-	fn encode(self, sink: &mut Sink) {
-		sink.preamble_mut().extend_from_slice(&util::pad_i64(self)[..]);
+	const IS_FIXED: bool = true;
+
+	#[cfg(feature = "alloc")]
+	fn abi_type() -> String {
+		"int64".into()
+	}
+}
+
+impl AbiEncode for i64 {
+	fn encode(&self, sink: &mut Sink) {
+		sink.write(&util::pad_i64(*self));
 	}
 
 	const IS_FIXED: bool = true;
 }
 
-macro_rules! abi_type_fixed_impl {
-	($num: expr) => {
-		impl AbiType for [u8; $num] {
-			fn decode(stream: &mut Stream) -> Result<Self, Error> {
+macro_rules! abi_type_uint_impl {
+	($t: ty, $name: expr, $bytes: expr, $err: expr) => {
+		impl<'a> AbiDecode<'a> for $t {
+			fn decode(stream: &mut Stream<'a>) -> Result<Self, Error> {
 				let previous_position = stream.advance(32)?;
 				let slice = &stream.payload()[previous_position..stream.position()];
-				let mut result = [0u8; $num];
-				result.copy_from_slice(&slice[0..$num]);
-				Ok(result)
+
+				if !slice[..32 - $bytes].iter().all(|x| *x == 0) {
+					return Err($err.into())
+				}
+
+				let mut result = 0u128;
+				for byte in slice[32 - $bytes..].iter() {
+					result = (result << 8) | *byte as u128;
+				}
+
+				Ok(result as $t)
+			}
+
+			const IS_FIXED: bool = true;
+
+			#[cfg(feature = "alloc")]
+			fn abi_type() -> String {
+				$name.into()
 			}
+		}
 
-			fn encode(self, sink: &mut Sink) {
-				let mut padded = [0u8; 32];
-				padded[0..$num].copy_from_slice(&self[..]);
-				sink.preamble_mut().extend_from_slice(&padded[..]);
+		impl AbiEncode for $t {
+			fn encode(&self, sink: &mut Sink) {
+				sink.write(&util::pad_u128(*self as u128));
 			}
 
 			const IS_FIXED: bool = true;
@@ -286,6 +443,190 @@ macro_rules! abi_type_fixed_impl {
 	}
 }
 
+abi_type_uint_impl!(u16, "uint16", 2, ErrorKind::InvalidU16);
+abi_type_uint_impl!(u128, "uint128", 16, ErrorKind::InvalidU128);
+
+macro_rules! abi_type_int_impl {
+	($t: ty, $name: expr, $bytes: expr) => {
+		impl<'a> AbiDecode<'a> for $t {
+			fn decode(stream: &mut Stream<'a>) -> Result<Self, Error> {
+				let previous_position = stream.advance(32)?;
+				let slice = &stream.payload()[previous_position..stream.position()];
+
+				// value has to be sign extended to the full 32 bytes
+				let is_negative = slice[32 - $bytes] & 0x80 != 0;
+				let padding = if is_negative { 0xff } else { 0 };
+				if !slice[..32 - $bytes].iter().all(|x| *x == padding) {
+					return Err(ErrorKind::InvalidPadding.into());
+				}
+
+				let mut result: i128 = if is_negative { -1 } else { 0 };
+				for byte in slice[32 - $bytes..].iter() {
+					result = (result << 8) | *byte as i128;
+				}
+
+				Ok(result as $t)
+			}
+
+			const IS_FIXED: bool = true;
+
+			#[cfg(feature = "alloc")]
+			fn abi_type() -> String {
+				$name.into()
+			}
+		}
+
+		impl AbiEncode for $t {
+			fn encode(&self, sink: &mut Sink) {
+				sink.write(&util::pad_i128(*self as i128));
+			}
+
+			const IS_FIXED: bool = true;
+		}
+	}
+}
+
+abi_type_int_impl!(i8, "int8", 1);
+abi_type_int_impl!(i16, "int16", 2);
+abi_type_int_impl!(i128, "int128", 16);
+
+impl<'a> AbiDecode<'a> for I256 {
+	fn decode(stream: &mut Stream<'a>) -> Result<Self, Error> {
+		Ok(I256::from_twos_complement(U256::decode(stream)?))
+	}
+
+	const IS_FIXED: bool = true;
+
+	#[cfg(feature = "alloc")]
+	fn abi_type() -> String {
+		"int256".into()
+	}
+}
+
+impl AbiEncode for I256 {
+	fn encode(&self, sink: &mut Sink) {
+		self.into_twos_complement().encode(sink)
+	}
+
+	const IS_FIXED: bool = true;
+}
+
+// References are encoded like the values they point to.
+macro_rules! abi_encode_ref_impl {
+	($($t: ty),+) => {
+		$(
+			impl<'a> AbiEncode for &'a $t {
+				fn encode(&self, sink: &mut Sink) {
+					(**self).encode(sink)
+				}
+
+				const IS_FIXED: bool = <$t as AbiEncode>::IS_FIXED;
+
+				fn head_size() -> usize {
+					<$t as AbiEncode>::head_size()
+				}
+			}
+		)+
+	}
+}
+
+abi_encode_ref_impl!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, bool, U256, I256, H160, H256, str);
+#[cfg(feature = "alloc")]
+abi_encode_ref_impl!(String);
+
+#[cfg(feature = "alloc")]
+impl<'a, T: AbiEncode> AbiEncode for &'a Vec<T> {
+	fn encode(&self, sink: &mut Sink) {
+		T::encode_slice(self, sink)
+	}
+
+	const IS_FIXED: bool = false;
+}
+
+impl<'a, T: AbiEncode> AbiEncode for &'a [T] {
+	fn encode(&self, sink: &mut Sink) {
+		T::encode_slice(self, sink)
+	}
+
+	const IS_FIXED: bool = false;
+}
+
+// `bytes` and `string` are decoded without copying as slices of the payload.
+impl<'a> AbiDecode<'a> for &'a [u8] {
+	fn decode(stream: &mut Stream<'a>) -> Result<Self, Error> {
+		let len = u32::decode(stream)? as usize;
+		stream.read_padded(len)
+	}
+
+	const IS_FIXED: bool = false;
+
+	#[cfg(feature = "alloc")]
+	fn abi_type() -> String {
+		"bytes".into()
+	}
+}
+
+impl<'a> AbiDecode<'a> for &'a str {
+	fn decode(stream: &mut Stream<'a>) -> Result<Self, Error> {
+		let len = u32::decode(stream)? as usize;
+		from_utf8(stream.read_padded(len)?).map_err(|_err| ErrorKind::InvalidUtf8.into())
+	}
+
+	const IS_FIXED: bool = false;
+
+	#[cfg(feature = "alloc")]
+	fn abi_type() -> String {
+		"string".into()
+	}
+}
+
+macro_rules! abi_type_array_impl {
+	($num: expr, $($elem: ident)+) => {
+		impl<'a, T: AbiDecode<'a>> AbiDecode<'a> for [T; $num] {
+			fn decode(stream: &mut Stream<'a>) -> Result<Self, Error> {
+				let mut elements: [Option<T>; $num] = Default::default();
+				T::decode_array(stream, &mut elements)?;
+				let mut elements = elements.iter_mut();
+				$(let $elem = elements.next().and_then(Option::take).ok_or(ErrorKind::Other)?;)+
+				Ok([$($elem),+])
+			}
+
+			// Array is encoded inline only if its elements are fixed,
+			// otherwise it is encoded like a tuple of its elements.
+			const IS_FIXED: bool = T::IS_FIXED;
+
+			#[cfg(feature = "alloc")]
+			fn abi_type() -> String {
+				T::abi_array_type($num)
+			}
+		}
+
+		impl<T: AbiEncode> AbiEncode for [T; $num] {
+			fn encode(&self, sink: &mut Sink) {
+				T::encode_array(self, sink)
+			}
+
+			const IS_FIXED: bool = T::IS_FIXED;
+
+			fn head_size() -> usize {
+				T::array_head_size($num)
+			}
+		}
+
+		impl<'a, T: AbiEncode> AbiEncode for &'a [T; $num] {
+			fn encode(&self, sink: &mut Sink) {
+				T::encode_array(*self, sink)
+			}
+
+			const IS_FIXED: bool = T::IS_FIXED;
+
+			fn head_size() -> usize {
+				T::array_head_size($num)
+			}
+		}
+	}
+}
+
 macro_rules! tuple_impls {
 	($(
 		$Tuple:ident {
@@ -293,17 +634,50 @@ macro_rules! tuple_impls {
 		}
 	)+) => {
 		$(
-			impl<$($T:AbiType),+> AbiType for ($($T,)+) {
-				fn decode(_stream: &mut Stream) -> Result<Self, Error> {
-					panic!("Tuples allow only encoding, not decoding (for supporting multiple return types)")
+			impl<'a, $($T:AbiDecode<'a>),+> AbiDecode<'a> for ($($T,)+) {
+				fn decode(stream: &mut Stream<'a>) -> Result<Self, Error> {
+					Ok(($(stream.pop::<$T>().map_err(|error| error.at_member($idx))?,)+))
+				}
+
+				// Tuple is encoded inline only if all of its members are fixed,
+				// otherwise it is encoded as a dynamic value with its own head and tail.
+				const IS_FIXED: bool = true $(& $T::IS_FIXED)+;
+
+				#[cfg(feature = "alloc")]
+				fn abi_type() -> String {
+					let members: &[String] = &[$($T::abi_type()),+];
+					format!("({})", members.join(","))
+				}
+			}
+
+			impl<$($T:AbiEncode),+> AbiEncode for ($($T,)+) {
+				fn encode(&self, sink: &mut Sink) {
+					if <Self as AbiEncode>::IS_FIXED {
+						$(sink.push_ref(&self.$idx);)+
+					} else {
+						sink.frame(0 $(+ $T::head_size())+, |sink| {
+							$(sink.push_ref(&self.$idx);)+
+						});
+					}
+				}
+
+				const IS_FIXED: bool = true $(& $T::IS_FIXED)+;
+
+				fn head_size() -> usize {
+					if <Self as AbiEncode>::IS_FIXED { 0 $(+ $T::head_size())+ } else { 32 }
 				}
+			}
 
-				fn encode(self, sink: &mut Sink) {
-					$(sink.push(self.$idx);)+
+			impl<'a, $($T:AbiEncode),+> AbiEncode for &'a ($($T,)+) {
+				fn encode(&self, sink: &mut Sink) {
+					(**self).encode(sink)
 				}
 
-				const IS_FIXED: bool = true;
+				const IS_FIXED: bool = <($($T,)+) as AbiEncode>::IS_FIXED;
 
+				fn head_size() -> usize {
+					<($($T,)+) as AbiEncode>::head_size()
+				}
 			}
 		)+
 	}
@@ -415,38 +789,38 @@ tuple_impls! {
 }
 
 
-abi_type_fixed_impl!(1);
-abi_type_fixed_impl!(2);
-abi_type_fixed_impl!(3);
-abi_type_fixed_impl!(4);
-abi_type_fixed_impl!(5);
-abi_type_fixed_impl!(6);
-abi_type_fixed_impl!(7);
-abi_type_fixed_impl!(8);
-abi_type_fixed_impl!(9);
-abi_type_fixed_impl!(10);
-abi_type_fixed_impl!(11);
-abi_type_fixed_impl!(12);
-abi_type_fixed_impl!(13);
-abi_type_fixed_impl!(14);
-abi_type_fixed_impl!(15);
-abi_type_fixed_impl!(16);
-abi_type_fixed_impl!(17);
-abi_type_fixed_impl!(18);
-abi_type_fixed_impl!(19);
-abi_type_fixed_impl!(20);
-abi_type_fixed_impl!(21);
-abi_type_fixed_impl!(22);
-abi_type_fixed_impl!(23);
-abi_type_fixed_impl!(24);
-abi_type_fixed_impl!(25);
-abi_type_fixed_impl!(26);
-abi_type_fixed_impl!(27);
-abi_type_fixed_impl!(28);
-abi_type_fixed_impl!(29);
-abi_type_fixed_impl!(30);
-abi_type_fixed_impl!(31);
-abi_type_fixed_impl!(32);
+abi_type_array_impl!(1, e0);
+abi_type_array_impl!(2, e0 e1);
+abi_type_array_impl!(3, e0 e1 e2);
+abi_type_array_impl!(4, e0 e1 e2 e3);
+abi_type_array_impl!(5, e0 e1 e2 e3 e4);
+abi_type_array_impl!(6, e0 e1 e2 e3 e4 e5);
+abi_type_array_impl!(7, e0 e1 e2 e3 e4 e5 e6);
+abi_type_array_impl!(8, e0 e1 e2 e3 e4 e5 e6 e7);
+abi_type_array_impl!(9, e0 e1 e2 e3 e4 e5 e6 e7 e8);
+abi_type_array_impl!(10, e0 e1 e2 e3 e4 e5 e6 e7 e8 e9);
+abi_type_array_impl!(11, e0 e1 e2 e3 e4 e5 e6 e7 e8 e9 e10);
+abi_type_array_impl!(12, e0 e1 e2 e3 e4 e5 e6 e7 e8 e9 e10 e11);
+abi_type_array_impl!(13, e0 e1 e2 e3 e4 e5 e6 e7 e8 e9 e10 e11 e12);
+abi_type_array_impl!(14, e0 e1 e2 e3 e4 e5 e6 e7 e8 e9 e10 e11 e12 e13);
+abi_type_array_impl!(15, e0 e1 e2 e3 e4 e5 e6 e7 e8 e9 e10 e11 e12 e13 e14);
+abi_type_array_impl!(16, e0 e1 e2 e3 e4 e5 e6 e7 e8 e9 e10 e11 e12 e13 e14 e15);
+abi_type_array_impl!(17, e0 e1 e2 e3 e4 e5 e6 e7 e8 e9 e10 e11 e12 e13 e14 e15 e16);
+abi_type_array_impl!(18, e0 e1 e2 e3 e4 e5 e6 e7 e8 e9 e10 e11 e12 e13 e14 e15 e16 e17);
+abi_type_array_impl!(19, e0 e1 e2 e3 e4 e5 e6 e7 e8 e9 e10 e11 e12 e13 e14 e15 e16 e17 e18);
+abi_type_array_impl!(20, e0 e1 e2 e3 e4 e5 e6 e7 e8 e9 e10 e11 e12 e13 e14 e15 e16 e17 e18 e19);
+abi_type_array_impl!(21, e0 e1 e2 e3 e4 e5 e6 e7 e8 e9 e10 e11 e12 e13 e14 e15 e16 e17 e18 e19 e20);
+abi_type_array_impl!(22, e0 e1 e2 e3 e4 e5 e6 e7 e8 e9 e10 e11 e12 e13 e14 e15 e16 e17 e18 e19 e20 e21);
+abi_type_array_impl!(23, e0 e1 e2 e3 e4 e5 e6 e7 e8 e9 e10 e11 e12 e13 e14 e15 e16 e17 e18 e19 e20 e21 e22);
+abi_type_array_impl!(24, e0 e1 e2 e3 e4 e5 e6 e7 e8 e9 e10 e11 e12 e13 e14 e15 e16 e17 e18 e19 e20 e21 e22 e23);
+abi_type_array_impl!(25, e0 e1 e2 e3 e4 e5 e6 e7 e8 e9 e10 e11 e12 e13 e14 e15 e16 e17 e18 e19 e20 e21 e22 e23 e24);
+abi_type_array_impl!(26, e0 e1 e2 e3 e4 e5 e6 e7 e8 e9 e10 e11 e12 e13 e14 e15 e16 e17 e18 e19 e20 e21 e22 e23 e24 e25);
+abi_type_array_impl!(27, e0 e1 e2 e3 e4 e5 e6 e7 e8 e9 e10 e11 e12 e13 e14 e15 e16 e17 e18 e19 e20 e21 e22 e23 e24 e25 e26);
+abi_type_array_impl!(28, e0 e1 e2 e3 e4 e5 e6 e7 e8 e9 e10 e11 e12 e13 e14 e15 e16 e17 e18 e19 e20 e21 e22 e23 e24 e25 e26 e27);
+abi_type_array_impl!(29, e0 e1 e2 e3 e4 e5 e6 e7 e8 e9 e10 e11 e12 e13 e14 e15 e16 e17 e18 e19 e20 e21 e22 e23 e24 e25 e26 e27 e28);
+abi_type_array_impl!(30, e0 e1 e2 e3 e4 e5 e6 e7 e8 e9 e10 e11 e12 e13 e14 e15 e16 e17 e18 e19 e20 e21 e22 e23 e24 e25 e26 e27 e28 e29);
+abi_type_array_impl!(31, e0 e1 e2 e3 e4 e5 e6 e7 e8 e9 e10 e11 e12 e13 e14 e15 e16 e17 e18 e19 e20 e21 e22 e23 e24 e25 e26 e27 e28 e29 e30);
+abi_type_array_impl!(32, e0 e1 e2 e3 e4 e5 e6 e7 e8 e9 e10 e11 e12 e13 e14 e15 e16 e17 e18 e19 e20 e21 e22 e23 e24 e25 e26 e27 e28 e29 e30 e31);
 
 #[cfg(test)]
 mod tests {
@@ -471,10 +845,10 @@ mod tests {
 			]
 		);
 
-		let mut sink = Sink::new(1);
+		let mut sink = Sink::new();
 		sink.push(val);
 
-		assert_eq!(&sink.finalize_panicking()[..], &data[..]);
+		assert_eq!(&sink.finalize().unwrap()[..], &data[..]);
 	}
 
 	#[test]
@@ -490,9 +864,9 @@ mod tests {
 
 		assert_eq!(val, [1u8, 2u8]);
 
-		let mut sink = Sink::new(1);
+		let mut sink = Sink::new();
 		sink.push(val);
 
-		assert_eq!(&sink.finalize_panicking()[..], &data[..]);
+		assert_eq!(&sink.finalize().unwrap()[..], &data[..]);
 	}
 }
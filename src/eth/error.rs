@@ -0,0 +1,281 @@
+//! Decoding errors
+
+use lib::*;
+use super::AbiDecode;
+
+/// Kind of the error for decoding rust types from stream
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+	/// Invalid bool for provided input
+	InvalidBool,
+	/// Invalid u32 for provided input
+	InvalidU32,
+	/// Invalid u64 for provided input
+	InvalidU64,
+	/// Invalid u8 for provided input
+	InvalidU8,
+	/// Invalid u16 for provided input
+	InvalidU16,
+	/// Invalid u128 for provided input
+	InvalidU128,
+	/// Unexpected end of the stream
+	UnexpectedEof,
+	/// Invalid padding for fixed type
+	InvalidPadding,
+	/// Offset of dynamic type points outside of the payload
+	InvalidOffset,
+	/// Offset of dynamic type points into the head or does not follow
+	/// the previous dynamic value (strict mode)
+	NonCanonicalOffset,
+	/// Padding of bytes, string or address is not zero (strict mode)
+	NonZeroPadding,
+	/// Payload continues after the decoded values (strict mode)
+	TrailingBytes,
+	/// String is not valid UTF-8
+	InvalidUtf8,
+	/// Log signature topic does not match the event
+	InvalidEventSignature,
+	/// Number of log topics does not match the event
+	InvalidTopics,
+	/// Other error
+	Other,
+}
+
+impl ErrorKind {
+	/// Static description of the error kind
+	pub fn description(&self) -> &'static str {
+		match *self {
+			ErrorKind::InvalidBool => "invalid bool",
+			ErrorKind::InvalidU32 => "invalid u32",
+			ErrorKind::InvalidU64 => "invalid u64",
+			ErrorKind::InvalidU8 => "invalid u8",
+			ErrorKind::InvalidU16 => "invalid u16",
+			ErrorKind::InvalidU128 => "invalid u128",
+			ErrorKind::UnexpectedEof => "unexpected end of payload",
+			ErrorKind::InvalidPadding => "invalid padding",
+			ErrorKind::InvalidOffset => "offset out of payload",
+			ErrorKind::NonCanonicalOffset => "non-canonical offset",
+			ErrorKind::NonZeroPadding => "non-zero padding",
+			ErrorKind::TrailingBytes => "trailing bytes",
+			ErrorKind::InvalidUtf8 => "invalid utf-8 string",
+			ErrorKind::InvalidEventSignature => "invalid event signature",
+			ErrorKind::InvalidTopics => "invalid number of topics",
+			ErrorKind::Other => "other error",
+		}
+	}
+}
+
+impl fmt::Display for ErrorKind {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(self.description())
+	}
+}
+
+/// Step of the path from the decoded arguments to the failed value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathSegment {
+	/// Argument of the method or event, displayed as `args[index]`
+	Argument(usize),
+	/// Element of an array, displayed as `[index]`
+	Index(usize),
+	/// Member of a Rust tuple, displayed as `.index`
+	Member(usize),
+	/// Field of a struct, displayed as `.name`
+	Field(&'static str),
+}
+
+impl fmt::Display for PathSegment {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			PathSegment::Argument(index) => write!(f, "args[{}]", index),
+			PathSegment::Index(index) => write!(f, "[{}]", index),
+			PathSegment::Member(index) => write!(f, ".{}", index),
+			PathSegment::Field(name) => write!(f, ".{}", name),
+		}
+	}
+}
+
+/// Number of the outermost segments of the path kept by an error
+pub const MAX_PATH_DEPTH: usize = 8;
+
+/// Error for decoding rust types from stream
+///
+/// Besides the kind, the error carries the byte position of the failed value,
+/// its expected ABI type and the path to it from the decoded arguments
+/// (e.g. `args[2][5].amount`), as far as they are known.
+///
+/// The path is kept without allocating, up to `MAX_PATH_DEPTH` segments.
+/// The expected type is only known with the `alloc` feature.
+///
+/// Errors compare equal to their `ErrorKind`. The kinds `Error` had as an enum
+/// before 0.3 are still available as constants like `Error::InvalidBool`, but
+/// can't be matched as patterns anymore, which is done by matching `kind()`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Error {
+	kind: ErrorKind,
+	position: Option<usize>,
+	#[cfg(feature = "alloc")]
+	expected: Option<String>,
+	path: [PathSegment; MAX_PATH_DEPTH],
+	depth: usize,
+}
+
+impl Error {
+	/// New error of the given kind without context
+	pub fn new(kind: ErrorKind) -> Self {
+		Error {
+			kind: kind,
+			position: None,
+			#[cfg(feature = "alloc")]
+			expected: None,
+			path: [PathSegment::Argument(0); MAX_PATH_DEPTH],
+			depth: 0,
+		}
+	}
+
+	/// Kind of the error
+	pub fn kind(&self) -> ErrorKind {
+		self.kind
+	}
+
+	/// Position of the failed value in the decoded payload
+	pub fn position(&self) -> Option<usize> {
+		self.position
+	}
+
+	/// ABI type of the failed value, e.g. `uint256[]`
+	#[cfg(feature = "alloc")]
+	pub fn expected(&self) -> Option<&str> {
+		self.expected.as_ref().map(|expected| &expected[..])
+	}
+
+	/// ABI type of the failed value, which is unknown without the `alloc` feature
+	#[cfg(not(feature = "alloc"))]
+	pub fn expected(&self) -> Option<&str> {
+		None
+	}
+
+	/// Path to the failed value, outermost segment first
+	pub fn path(&self) -> &[PathSegment] {
+		&self.path[..self.depth]
+	}
+
+	/// Error within the argument at `index`
+	pub fn at_argument(self, index: usize) -> Self {
+		self.within(PathSegment::Argument(index))
+	}
+
+	/// Error within the array element at `index`
+	pub fn at_index(self, index: usize) -> Self {
+		self.within(PathSegment::Index(index))
+	}
+
+	/// Error within the tuple member at `index`
+	pub fn at_member(self, index: usize) -> Self {
+		self.within(PathSegment::Member(index))
+	}
+
+	/// Error within the struct field `name`
+	pub fn at_field(self, name: &'static str) -> Self {
+		self.within(PathSegment::Field(name))
+	}
+
+	/// Prepends `segment` to the path, dropping its innermost segment if it is full
+	fn within(mut self, segment: PathSegment) -> Self {
+		let depth = cmp::min(self.depth + 1, MAX_PATH_DEPTH);
+		for index in (1..depth).rev() {
+			self.path[index] = self.path[index - 1];
+		}
+		self.path[0] = segment;
+		self.depth = depth;
+		self
+	}
+
+	/// Sets position and expected type `T` unless the error was already
+	/// located by decoding of a more nested value
+	pub(crate) fn locate<'a, T: AbiDecode<'a>>(mut self, position: usize) -> Self {
+		if self.position.is_none() {
+			self.position = Some(position);
+			#[cfg(feature = "alloc")]
+			{
+				let expected = T::abi_type();
+				if !expected.is_empty() {
+					self.expected = Some(expected);
+				}
+			}
+		}
+		self
+	}
+}
+
+/// Declares the errors of the given kinds without context as constants of
+/// `Error`, which used to be an enum of these kinds before 0.3
+macro_rules! error_kind_consts {
+	($($kind:ident),*) => {
+		#[allow(non_upper_case_globals)]
+		impl Error {
+			$(
+				/// Error of the kind of the same name without context
+				pub const $kind: Error = Error {
+					kind: ErrorKind::$kind,
+					position: None,
+					#[cfg(feature = "alloc")]
+					expected: None,
+					path: [PathSegment::Argument(0); MAX_PATH_DEPTH],
+					depth: 0,
+				};
+			)*
+		}
+	}
+}
+
+error_kind_consts!(InvalidBool, InvalidU32, InvalidU64, UnexpectedEof, InvalidPadding, Other);
+
+impl PartialEq<ErrorKind> for Error {
+	fn eq(&self, kind: &ErrorKind) -> bool {
+		self.kind == *kind
+	}
+}
+
+impl fmt::Debug for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("Error")
+			.field("kind", &self.kind)
+			.field("position", &self.position)
+			.field("expected", &self.expected())
+			.field("path", &self.path())
+			.finish()
+	}
+}
+
+impl From<ErrorKind> for Error {
+	fn from(kind: ErrorKind) -> Self {
+		Error::new(kind)
+	}
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", self.kind)?;
+		if let Some(expected) = self.expected() {
+			write!(f, " decoding {}", expected)?;
+		}
+		if let Some(position) = self.position {
+			write!(f, " at byte {}", position)?;
+		}
+		if self.depth > 0 {
+			f.write_str(" in ")?;
+			for segment in self.path().iter() {
+				write!(f, "{}", segment)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for Error {
+	fn description(&self) -> &str {
+		self.kind.description()
+	}
+}
@@ -0,0 +1,102 @@
+//! Lazy array module
+
+use lib::*;
+use super::{AbiDecode, AbiEncode, Error, ErrorKind, Sink, Stream};
+
+/// Lazy iterator over the elements of a dynamic array `T[]` in the payload
+///
+/// Elements are decoded one at a time as the iterator advances instead of
+/// being collected into a `Vec`. They are validated once when the array is
+/// decoded, since the end of the array is only known after decoding all of
+/// them, so iterating over a decoded array yields no errors.
+pub struct ArrayIter<'a, T> {
+	elements: Stream<'a>,
+	index: usize,
+	len: usize,
+	_marker: PhantomData<T>,
+}
+
+impl<'a, T> Clone for ArrayIter<'a, T> {
+	fn clone(&self) -> Self {
+		ArrayIter {
+			elements: self.elements.clone(),
+			index: self.index,
+			len: self.len,
+			_marker: PhantomData,
+		}
+	}
+}
+
+impl<'a, T: AbiDecode<'a>> Iterator for ArrayIter<'a, T> {
+	type Item = Result<T, Error>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.index == self.len {
+			return None;
+		}
+		let index = self.index;
+		self.index += 1;
+		let result = self.elements.pop().map_err(|error| error.at_index(index));
+		if result.is_err() {
+			self.index = self.len;
+		}
+		Some(result)
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		let remaining = self.len - self.index;
+		(remaining, Some(remaining))
+	}
+}
+
+impl<'a, T: AbiDecode<'a>> ExactSizeIterator for ArrayIter<'a, T> {}
+
+impl<'a, T: AbiDecode<'a>> AbiDecode<'a> for ArrayIter<'a, T> {
+	fn decode(stream: &mut Stream<'a>) -> Result<Self, Error> {
+		let len = u32::decode(stream)? as usize;
+		// Every element occupies at least one word, so the length
+		// can't exceed the rest of the payload.
+		if len > stream.remaining() / 32 {
+			return Err(ErrorKind::UnexpectedEof.into());
+		}
+		// Elements are encoded like a tuple following the length,
+		// so offsets of dynamic elements are relative to the first element.
+		let iter = ArrayIter {
+			elements: stream.nested(stream.position())?,
+			index: 0,
+			len: len,
+			_marker: PhantomData,
+		};
+		let mut validated = iter.clone();
+		for element in validated.by_ref() {
+			element?;
+		}
+		stream.skip_nested(&validated.elements)?;
+		Ok(iter)
+	}
+
+	const IS_FIXED: bool = false;
+
+	// Elements are always decoded one by one, so `ArrayIter<u8>` is `uint8[]`
+	#[cfg(feature = "alloc")]
+	fn abi_type() -> String {
+		format!("{}[]", T::abi_type())
+	}
+}
+
+/// Encodes the elements not iterated over yet as `T[]`
+impl<'a, T: AbiDecode<'a> + AbiEncode> AbiEncode for ArrayIter<'a, T> {
+	fn encode(&self, sink: &mut Sink) {
+		let len = self.len - self.index;
+		sink.push(len as u32);
+		sink.frame(len * T::head_size(), |sink| {
+			// Elements were validated by decoding, an element failing anyway
+			// ends the iteration and the frame is reported as underflowed.
+			for element in self.clone().filter_map(Result::ok) {
+				sink.push(element);
+			}
+		});
+	}
+
+	const IS_FIXED: bool = false;
+}
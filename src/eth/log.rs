@@ -1,13 +1,29 @@
 //! Log module
 
 use byteorder::{BigEndian, ByteOrder};
+use lib::*;
 use super::types::*;
+use super::util;
+#[cfg(feature = "alloc")]
+use super::AbiEncodePacked;
 
 /// As log trait for how primitive types are represented as indexed arguments
 /// of the event log
+///
+/// Value types are stored in the topic like their encoding, `bytesN` left aligned.
+/// Dynamic types, arrays and tuples (or structs) are stored as the Keccak hash
+/// of their packed encoding, so their value can't be recovered from the log.
 pub trait AsLog {
 	/// Convert type to hash representation for the event log.
 	fn as_log(&self) -> H256;
+
+	/// Convert the fixed array `[T; N]` of this type to hash representation for the event log
+	/// Overridden by `u8` for `[u8; N]` to be stored as `bytesN`
+	#[doc(hidden)]
+	#[cfg(feature = "alloc")]
+	fn array_as_log(elements: &[Self]) -> H256 where Self: Sized + AbiEncodePacked {
+		util::keccak(&super::encode_packed(elements))
+	}
 }
 
 impl AsLog for u32 {
@@ -28,17 +44,13 @@ impl AsLog for u64 {
 
 impl AsLog for i64 {
 	fn as_log(&self) -> H256 {
-		let mut result = H256::zero();
-		BigEndian::write_i64(&mut result.as_mut()[24..32], *self);
-		result
+		util::pad_i64(*self).into()
 	}
 }
 
 impl AsLog for i32 {
 	fn as_log(&self) -> H256 {
-		let mut result = H256::zero();
-		BigEndian::write_i32(&mut result.as_mut()[28..32], *self);
-		result
+		util::pad_i32(*self).into()
 	}
 }
 
@@ -46,7 +58,7 @@ impl AsLog for i32 {
 impl AsLog for bool {
 	fn as_log(&self) -> H256 {
 		let mut result = H256::zero();
-		result.as_mut()[32] = if *self { 1 } else { 0 };
+		result.as_mut()[31] = if *self { 1 } else { 0 };
 		result
 	}
 }
@@ -70,3 +82,127 @@ impl AsLog for Address {
 		(*self).into()
 	}
 }
+
+macro_rules! as_log_uint_impl {
+	($t: ty) => {
+		impl AsLog for $t {
+			fn as_log(&self) -> H256 {
+				util::pad_u128(*self as u128).into()
+			}
+		}
+	}
+}
+
+as_log_uint_impl!(u16);
+as_log_uint_impl!(u128);
+
+macro_rules! as_log_int_impl {
+	($t: ty) => {
+		impl AsLog for $t {
+			fn as_log(&self) -> H256 {
+				util::pad_i128(*self as i128).into()
+			}
+		}
+	}
+}
+
+as_log_int_impl!(i8);
+as_log_int_impl!(i16);
+as_log_int_impl!(i128);
+
+impl AsLog for I256 {
+	fn as_log(&self) -> H256 {
+		self.into_twos_complement().as_log()
+	}
+}
+
+impl AsLog for u8 {
+	fn as_log(&self) -> H256 {
+		util::pad_u128(*self as u128).into()
+	}
+
+	#[cfg(feature = "alloc")]
+	fn array_as_log(elements: &[Self]) -> H256 {
+		let mut result = H256::zero();
+		result.as_mut()[..elements.len()].copy_from_slice(elements);
+		result
+	}
+}
+
+impl AsLog for str {
+	fn as_log(&self) -> H256 {
+		util::keccak(self.as_bytes())
+	}
+}
+
+#[cfg(feature = "alloc")]
+impl AsLog for String {
+	fn as_log(&self) -> H256 {
+		self.as_str().as_log()
+	}
+}
+
+// Arrays are hashed as the concatenation of their elements, each padded to 32 bytes,
+// and `[u8]` as the bytes themselves.
+#[cfg(feature = "alloc")]
+impl<T: AbiEncodePacked> AsLog for [T] {
+	fn as_log(&self) -> H256 {
+		util::keccak(&super::encode_packed(self))
+	}
+}
+
+#[cfg(feature = "alloc")]
+impl<T: AbiEncodePacked> AsLog for Vec<T> {
+	fn as_log(&self) -> H256 {
+		self.as_slice().as_log()
+	}
+}
+
+// References are stored like the values they point to.
+impl<'a, T: AsLog + ?Sized> AsLog for &'a T {
+	fn as_log(&self) -> H256 {
+		(**self).as_log()
+	}
+}
+
+macro_rules! as_log_array_impl {
+	($($num: expr)+) => {
+		$(
+			#[cfg(feature = "alloc")]
+			impl<T: AsLog + AbiEncodePacked> AsLog for [T; $num] {
+				fn as_log(&self) -> H256 {
+					T::array_as_log(self)
+				}
+			}
+		)+
+	}
+}
+
+as_log_array_impl!(1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31 32);
+
+// Tuples are hashed as the concatenation of their members, each padded to 32 bytes.
+macro_rules! as_log_tuple_impl {
+	($($T: ident),+) => {
+		#[cfg(feature = "alloc")]
+		impl<$($T: AbiEncodePacked),+> AsLog for ($($T,)+) {
+			fn as_log(&self) -> H256 {
+				let mut encoded = Vec::new();
+				self.encode_packed_element(&mut encoded);
+				util::keccak(&encoded)
+			}
+		}
+	}
+}
+
+as_log_tuple_impl!(A);
+as_log_tuple_impl!(A, B);
+as_log_tuple_impl!(A, B, C);
+as_log_tuple_impl!(A, B, C, D);
+as_log_tuple_impl!(A, B, C, D, E);
+as_log_tuple_impl!(A, B, C, D, E, F);
+as_log_tuple_impl!(A, B, C, D, E, F, G);
+as_log_tuple_impl!(A, B, C, D, E, F, G, H);
+as_log_tuple_impl!(A, B, C, D, E, F, G, H, I);
+as_log_tuple_impl!(A, B, C, D, E, F, G, H, I, J);
+as_log_tuple_impl!(A, B, C, D, E, F, G, H, I, J, K);
+as_log_tuple_impl!(A, B, C, D, E, F, G, H, I, J, K, L);
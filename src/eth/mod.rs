@@ -3,37 +3,167 @@
 #![warn(missing_docs)]
 
 mod util;
+mod error;
 mod log;
 mod stream;
+mod iter;
 mod sink;
+#[cfg(feature = "alloc")]
+mod packed;
 mod common;
+mod revert;
 #[cfg(test)]
 mod tests;
 
+pub use self::error::{Error, ErrorKind, PathSegment, MAX_PATH_DEPTH};
 pub use self::log::AsLog;
+pub use self::util::keccak;
 pub use self::stream::Stream;
-pub use self::sink::Sink;
+pub use self::iter::ArrayIter;
+pub use self::sink::{Sink, SinkError};
+#[cfg(feature = "alloc")]
+pub use self::packed::{AbiEncodePacked, encode_packed};
+pub use self::revert::{revert, DEFAULT_RETURN_CAPACITY, ERROR_REASON_SELECTOR};
+#[cfg(feature = "alloc")]
+pub use self::revert::{encode_revert_reason, encode_custom_error, CallError};
+#[cfg(feature = "std")]
+pub use self::revert::Revert;
 
 use super::types;
 
-/// Error for decoding rust types from stream
-#[derive(Debug, PartialEq, Eq)]
-pub enum Error {
-	/// Invalid bool for provided input
-	InvalidBool,
-	/// Invalid u32 for provided input
-	InvalidU32,
-	/// Invalid u64 for provided input
-	InvalidU64,
-	/// Unexpected end of the stream
-	UnexpectedEof,
-	/// Invalid padding for fixed type
-	InvalidPadding,
-	/// Other error
-	Other,
+/// Type encoded by reference to the data sink
+pub trait AbiEncode {
+	/// Push type to data sink
+	/// Should never be called manually! Use sink.push(val) or sink.push_ref(&val)
+	fn encode(&self, sink: &mut Sink);
+
+	/// Whether type has fixed length or not
+	const IS_FIXED: bool;
+
+	/// Number of bytes the type takes in the heads of a sequence of values,
+	/// which is the whole encoding for fixed types and the offset for dynamic ones
+	fn head_size() -> usize {
+		32
+	}
+
+	/// Number of bytes the fixed array `[T; len]` takes in the heads of a sequence of values
+	/// Overridden by `u8` for `[u8; N]` to take a single word as `bytesN`
+	#[doc(hidden)]
+	fn array_head_size(len: usize) -> usize {
+		if Self::IS_FIXED { len * Self::head_size() } else { 32 }
+	}
+
+	/// Push elements of the fixed array `[T; N]` to data sink
+	/// Should never be called manually! Use sink.push(&array)
+	/// Overridden by `u8` for `[u8; N]` to be encoded as `bytesN`
+	#[doc(hidden)]
+	fn encode_array(elements: &[Self], sink: &mut Sink) where Self: Sized {
+		if Self::IS_FIXED {
+			for element in elements.iter() {
+				sink.push_ref(element);
+			}
+		} else {
+			// Array of dynamic elements is encoded like a tuple of its elements.
+			sink.frame(elements.len() * Self::head_size(), |sink| {
+				for element in elements.iter() {
+					sink.push_ref(element);
+				}
+			});
+		}
+	}
+
+	/// Push slice of the type to data sink
+	/// Should never be called manually! Use sink.push(&vec)
+	/// Overridden by `u8` for `[u8]` to be encoded as `bytes`
+	#[doc(hidden)]
+	fn encode_slice(elements: &[Self], sink: &mut Sink) where Self: Sized {
+		sink.push(elements.len() as u32);
+
+		// Elements are encoded like a tuple following the length,
+		// so offsets of dynamic elements are relative to the first element.
+		sink.frame(elements.len() * Self::head_size(), |sink| {
+			for element in elements.iter() {
+				sink.push_ref(element);
+			}
+		});
+	}
+}
+
+/// Type decoded from the data stream
+///
+/// Borrowed types like `&'a [u8]`, `&'a str` and `ArrayIter<'a, T>` are
+/// decoded without copying, tied to the lifetime `'a` of the payload.
+pub trait AbiDecode<'a> : Sized {
+	/// Insantiate type from data stream
+	/// Should never be called manually! Use stream.pop()
+	fn decode(stream: &mut Stream<'a>) -> Result<Self, Error>;
+
+	/// Whether type has fixed length or not
+	const IS_FIXED: bool;
+
+	/// Name of the type in the ABI (e.g. `uint256[]`) to describe decoding errors
+	/// Empty if unknown
+	#[cfg(feature = "alloc")]
+	fn abi_type() -> ::lib::String {
+		::lib::String::new()
+	}
+
+	/// Name of the vector of the type in the ABI
+	/// Overridden by `u8` for `Vec<u8>` to be named `bytes`
+	#[doc(hidden)]
+	#[cfg(feature = "alloc")]
+	fn abi_vec_type() -> ::lib::String {
+		format!("{}[]", Self::abi_type())
+	}
+
+	/// Name of the fixed array `[T; len]` of the type in the ABI
+	/// Overridden by `u8` for `[u8; N]` to be named `bytesN`
+	#[doc(hidden)]
+	#[cfg(feature = "alloc")]
+	fn abi_array_type(len: usize) -> ::lib::String {
+		format!("{}[{}]", Self::abi_type(), len)
+	}
+
+	/// Insantiate vector of the type from data stream
+	/// Should never be called manually! Use stream.pop::<Vec<T>>()
+	/// Overridden by `u8` for `Vec<u8>` to be decoded as `bytes`
+	#[doc(hidden)]
+	#[cfg(feature = "alloc")]
+	fn decode_vec(stream: &mut Stream<'a>) -> Result<::lib::Vec<Self>, Error> {
+		let len = u32::decode(stream)? as usize;
+		// Every element occupies at least one word, so the length
+		// can't exceed the rest of the payload.
+		if len > stream.remaining() / 32 {
+			return Err(ErrorKind::UnexpectedEof.into());
+		}
+		stream.charge(len)?;
+		// Elements are encoded like a tuple following the length,
+		// so offsets of dynamic elements are relative to the first element.
+		let mut elements = stream.nested(stream.position())?;
+		let mut result = ::lib::Vec::with_capacity(len);
+		for index in 0..len {
+			result.push(elements.pop().map_err(|error| error.at_index(index))?);
+		}
+		stream.skip_nested(&elements)?;
+		Ok(result)
+	}
+
+	/// Insantiate the `elements` of the fixed array `[T; N]` from data stream
+	/// Should never be called manually! Use stream.pop::<[T; N]>()
+	/// Overridden by `u8` for `[u8; N]` to be decoded as `bytesN`
+	#[doc(hidden)]
+	fn decode_array(stream: &mut Stream<'a>, elements: &mut [Option<Self>]) -> Result<(), Error> {
+		for (index, element) in elements.iter_mut().enumerate() {
+			*element = Some(stream.pop().map_err(|error| error.at_index(index))?);
+		}
+		Ok(())
+	}
 }
 
 /// Abi type trait
+///
+/// Superseded by `AbiEncode` and `AbiDecode`, which are implemented
+/// for every `AbiType`. Encoding goes through a clone of the value.
 pub trait AbiType : Sized {
 	/// Insantiate type from data stream
 	/// Should never be called manually! Use stream.pop()
@@ -47,11 +177,109 @@ pub trait AbiType : Sized {
 	const IS_FIXED: bool;
 }
 
+impl<T: AbiType + Clone> AbiEncode for T {
+	fn encode(&self, sink: &mut Sink) {
+		AbiType::encode(self.clone(), sink)
+	}
+
+	const IS_FIXED: bool = <T as AbiType>::IS_FIXED;
+}
+
+impl<'a, T: AbiType> AbiDecode<'a> for T {
+	fn decode(stream: &mut Stream<'a>) -> Result<Self, Error> {
+		<T as AbiType>::decode(stream)
+	}
+
+	const IS_FIXED: bool = <T as AbiType>::IS_FIXED;
+}
+
+/// Error for dispatching payload to the contract methods
+#[derive(Debug, PartialEq, Eq)]
+pub enum DispatchError {
+	/// Payload does not start with a method selector of the contract
+	InvalidSelector,
+	/// Payload is too short to contain a method selector
+	ShortPayload,
+	/// Argument at `index` failed to decode
+	ArgumentDecode {
+		/// Index of the argument
+		index: usize,
+		/// Error of decoding
+		error: Error,
+	},
+	/// Payload failed to decode after the arguments,
+	/// e.g. trailing bytes of a strict endpoint
+	Decode(Error),
+	/// Value was sent to a non-payable method
+	ValueNotAccepted,
+	/// Method returned an error, carrying the encoded revert payload
+	Revert(types::Vec<u8>),
+	/// Output of the method failed to encode
+	Encode(SinkError),
+}
+
+impl DispatchError {
+	/// Static description of the error
+	pub fn description(&self) -> &'static str {
+		match *self {
+			DispatchError::InvalidSelector => "Invalid method signature",
+			DispatchError::ShortPayload => "Invalid abi invoke",
+			DispatchError::ArgumentDecode { .. } => "argument decoding failed",
+			DispatchError::Decode(_) => "payload decoding failed",
+			DispatchError::ValueNotAccepted => "Unable to accept value in non-payable call",
+			DispatchError::Revert(_) => "Method reverted",
+			DispatchError::Encode(_) => "output encoding failed",
+		}
+	}
+
+	/// Revert payload to return to the caller
+	/// Errors other than `Revert` are reported as `Error(string)` with their description
+	#[cfg(feature = "alloc")]
+	pub fn into_revert_payload(self) -> ::lib::Vec<u8> {
+		match self {
+			DispatchError::Revert(payload) => payload,
+			other => encode_revert_reason(other.description()),
+		}
+	}
+
+	/// Abort the execution with the revert payload of `into_revert_payload`
+	/// The payload is encoded without allocating
+	pub fn revert(self) -> ! {
+		match self {
+			DispatchError::Revert(payload) => revert(&payload),
+			other => revert::revert_with_description(other.description()),
+		}
+	}
+}
+
+impl From<Error> for DispatchError {
+	fn from(error: Error) -> Self {
+		DispatchError::Decode(error)
+	}
+}
+
 /// Endpoint interface for contracts
 pub trait EndpointInterface {
 	/// Dispatch payload for regular method
-	fn dispatch(&mut self, payload: &[u8]) -> ::lib::Vec<u8>;
+	/// Reverts with the revert payload of the error if the payload can't be dispatched
+	fn dispatch(&mut self, payload: &[u8]) -> types::Vec<u8> {
+		match self.try_dispatch(payload) {
+			Ok(result) => result,
+			Err(err) => err.revert(),
+		}
+	}
+
+	/// Dispatch constructor payload
+	/// Reverts with the revert payload of the error if the payload can't be dispatched
+	fn dispatch_ctor(&mut self, payload: &[u8]) {
+		if let Err(err) = self.try_dispatch_ctor(payload) {
+			err.revert();
+		}
+	}
+
+	/// Dispatch payload for regular method
+	fn try_dispatch(&mut self, payload: &[u8]) -> Result<types::Vec<u8>, DispatchError>;
 
 	/// Dispatch constructor payload
-	fn dispatch_ctor(&mut self, payload: &[u8]);
+	fn try_dispatch_ctor(&mut self, payload: &[u8]) -> Result<(), DispatchError>;
 }
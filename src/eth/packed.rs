@@ -0,0 +1,223 @@
+//! Packed encoding module
+
+use lib::*;
+use super::types::*;
+use super::util;
+
+/// Type encoded in the non-standard packed mode of `abi.encodePacked`
+///
+/// Values are encoded with their minimal size and without offsets or lengths:
+/// `uint16` takes 2 bytes, `address` 20 bytes and `bytes` or `string` only their content.
+/// Elements of arrays are padded to a multiple of 32 bytes, like the in-place
+/// encoding Solidity hashes for indexed event parameters of dynamic types.
+pub trait AbiEncodePacked {
+	/// Append packed encoding of the value to `out`
+	fn encode_packed(&self, out: &mut Vec<u8>);
+
+	/// Append packed encoding of the value as an element of an array to `out`
+	/// Overridden by fixed types to be padded to 32 bytes
+	#[doc(hidden)]
+	fn encode_packed_element(&self, out: &mut Vec<u8>) {
+		self.encode_packed(out)
+	}
+
+	/// Append packed encoding of the array `[T]` to `out`
+	/// Overridden by `u8` for `[u8]` to be encoded as `bytes`
+	#[doc(hidden)]
+	fn encode_packed_slice(elements: &[Self], out: &mut Vec<u8>) where Self: Sized {
+		for element in elements.iter() {
+			element.encode_packed_element(out);
+		}
+	}
+
+	/// Append packed encoding of the array `[T]` as an element of an array to `out`
+	/// Overridden by `u8` for `[u8]` to be encoded as padded `bytes`
+	#[doc(hidden)]
+	fn encode_packed_slice_element(elements: &[Self], out: &mut Vec<u8>) where Self: Sized {
+		Self::encode_packed_slice(elements, out)
+	}
+
+	/// Append packed encoding of the fixed array `[T; N]` as an element of an array to `out`
+	/// Overridden by `u8` for `[u8; N]` to be encoded as padded `bytesN`
+	#[doc(hidden)]
+	fn encode_packed_array_element(elements: &[Self], out: &mut Vec<u8>) where Self: Sized {
+		Self::encode_packed_slice(elements, out)
+	}
+}
+
+/// Packed encoding of `value` like `abi.encodePacked`
+///
+/// Multiple values are encoded together as a tuple, e.g. `encode_packed(&(a, b))`.
+pub fn encode_packed<T: AbiEncodePacked + ?Sized>(value: &T) -> Vec<u8> {
+	let mut out = Vec::new();
+	value.encode_packed(&mut out);
+	out
+}
+
+// Packed form of fixed types is the significant part of their 32 byte word,
+// which is also their encoding as an element of an array.
+macro_rules! abi_packed_word_impl {
+	($t: ty, $bytes: expr, $value: ident => $word: expr) => {
+		impl AbiEncodePacked for $t {
+			fn encode_packed(&self, out: &mut Vec<u8>) {
+				let $value = self;
+				out.extend_from_slice(&$word[32 - $bytes..]);
+			}
+
+			fn encode_packed_element(&self, out: &mut Vec<u8>) {
+				let $value = self;
+				out.extend_from_slice(&$word);
+			}
+		}
+	}
+}
+
+abi_packed_word_impl!(bool, 1, value => util::pad_u32(*value as u32));
+abi_packed_word_impl!(u16, 2, value => util::pad_u128(*value as u128));
+abi_packed_word_impl!(u32, 4, value => util::pad_u32(*value));
+abi_packed_word_impl!(u64, 8, value => util::pad_u64(*value));
+abi_packed_word_impl!(u128, 16, value => util::pad_u128(*value));
+abi_packed_word_impl!(i8, 1, value => util::pad_i128(*value as i128));
+abi_packed_word_impl!(i16, 2, value => util::pad_i128(*value as i128));
+abi_packed_word_impl!(i32, 4, value => util::pad_i32(*value));
+abi_packed_word_impl!(i64, 8, value => util::pad_i64(*value));
+abi_packed_word_impl!(i128, 16, value => util::pad_i128(*value));
+abi_packed_word_impl!(U256, 32, value => {
+	let mut word = [0u8; 32];
+	value.to_big_endian(&mut word);
+	word
+});
+abi_packed_word_impl!(I256, 32, value => {
+	let mut word = [0u8; 32];
+	value.into_twos_complement().to_big_endian(&mut word);
+	word
+});
+abi_packed_word_impl!(H160, 20, value => *H256::from(*value).as_fixed_bytes());
+abi_packed_word_impl!(H256, 32, value => *value.as_fixed_bytes());
+
+impl AbiEncodePacked for u8 {
+	fn encode_packed(&self, out: &mut Vec<u8>) {
+		out.push(*self);
+	}
+
+	fn encode_packed_element(&self, out: &mut Vec<u8>) {
+		out.extend_from_slice(&util::pad_u32(*self as u32));
+	}
+
+	// `[u8]` is `bytes` rather than `uint8[]`
+	fn encode_packed_slice(elements: &[Self], out: &mut Vec<u8>) {
+		out.extend_from_slice(elements);
+	}
+
+	fn encode_packed_slice_element(elements: &[Self], out: &mut Vec<u8>) {
+		write_padded(elements, out)
+	}
+
+	// `[u8; N]` is `bytesN`, which is left aligned in the padded word
+	fn encode_packed_array_element(elements: &[Self], out: &mut Vec<u8>) {
+		write_padded(elements, out)
+	}
+}
+
+fn write_padded(bytes: &[u8], out: &mut Vec<u8>) {
+	out.extend_from_slice(bytes);
+	let padding = (32 - bytes.len() % 32) % 32;
+	out.extend_from_slice(&[0u8; 32][..padding]);
+}
+
+impl AbiEncodePacked for str {
+	fn encode_packed(&self, out: &mut Vec<u8>) {
+		out.extend_from_slice(self.as_bytes());
+	}
+
+	fn encode_packed_element(&self, out: &mut Vec<u8>) {
+		write_padded(self.as_bytes(), out)
+	}
+}
+
+impl AbiEncodePacked for String {
+	fn encode_packed(&self, out: &mut Vec<u8>) {
+		self.as_str().encode_packed(out)
+	}
+
+	fn encode_packed_element(&self, out: &mut Vec<u8>) {
+		self.as_str().encode_packed_element(out)
+	}
+}
+
+impl<T: AbiEncodePacked> AbiEncodePacked for [T] {
+	fn encode_packed(&self, out: &mut Vec<u8>) {
+		T::encode_packed_slice(self, out)
+	}
+
+	fn encode_packed_element(&self, out: &mut Vec<u8>) {
+		T::encode_packed_slice_element(self, out)
+	}
+}
+
+impl<T: AbiEncodePacked> AbiEncodePacked for Vec<T> {
+	fn encode_packed(&self, out: &mut Vec<u8>) {
+		T::encode_packed_slice(self, out)
+	}
+
+	fn encode_packed_element(&self, out: &mut Vec<u8>) {
+		T::encode_packed_slice_element(self, out)
+	}
+}
+
+// References are encoded like the values they point to.
+impl<'a, T: AbiEncodePacked + ?Sized> AbiEncodePacked for &'a T {
+	fn encode_packed(&self, out: &mut Vec<u8>) {
+		(**self).encode_packed(out)
+	}
+
+	fn encode_packed_element(&self, out: &mut Vec<u8>) {
+		(**self).encode_packed_element(out)
+	}
+}
+
+macro_rules! abi_packed_array_impl {
+	($($num: expr)+) => {
+		$(
+			impl<T: AbiEncodePacked> AbiEncodePacked for [T; $num] {
+				fn encode_packed(&self, out: &mut Vec<u8>) {
+					T::encode_packed_slice(self, out)
+				}
+
+				fn encode_packed_element(&self, out: &mut Vec<u8>) {
+					T::encode_packed_array_element(self, out)
+				}
+			}
+		)+
+	}
+}
+
+abi_packed_array_impl!(1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31 32);
+
+// Tuples are the concatenation of their members, like the arguments of `abi.encodePacked`.
+macro_rules! abi_packed_tuple_impl {
+	($($T: ident $idx: tt),+) => {
+		impl<$($T: AbiEncodePacked),+> AbiEncodePacked for ($($T,)+) {
+			fn encode_packed(&self, out: &mut Vec<u8>) {
+				$(self.$idx.encode_packed(out);)+
+			}
+
+			fn encode_packed_element(&self, out: &mut Vec<u8>) {
+				$(self.$idx.encode_packed_element(out);)+
+			}
+		}
+	}
+}
+
+abi_packed_tuple_impl!(A 0);
+abi_packed_tuple_impl!(A 0, B 1);
+abi_packed_tuple_impl!(A 0, B 1, C 2);
+abi_packed_tuple_impl!(A 0, B 1, C 2, D 3);
+abi_packed_tuple_impl!(A 0, B 1, C 2, D 3, E 4);
+abi_packed_tuple_impl!(A 0, B 1, C 2, D 3, E 4, F 5);
+abi_packed_tuple_impl!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+abi_packed_tuple_impl!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+abi_packed_tuple_impl!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8);
+abi_packed_tuple_impl!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9);
+abi_packed_tuple_impl!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10);
+abi_packed_tuple_impl!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11);
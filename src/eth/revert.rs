@@ -0,0 +1,196 @@
+//! Solidity-compatible revert payloads
+
+use lib::*;
+use super::Sink;
+#[cfg(feature = "alloc")]
+use super::{util, AbiDecode, AbiEncode, Error, ErrorKind, SinkError, Stream};
+
+/// Selector of the Solidity `Error(string)` revert reason
+pub const ERROR_REASON_SELECTOR: u32 = 0x08c379a0;
+
+/// Size of the output buffer of generated clients unless set by their `return_capacity`
+pub const DEFAULT_RETURN_CAPACITY: usize = 1024;
+
+#[cfg(feature = "alloc")]
+fn read_selector(payload: &[u8]) -> Option<u32> {
+	if payload.len() < 4 {
+		return None;
+	}
+	Some(
+		((payload[0] as u32) << 24)
+			+ ((payload[1] as u32) << 16)
+			+ ((payload[2] as u32) << 8)
+			+ (payload[3] as u32)
+	)
+}
+
+/// Encode revert payload of the Solidity `Error(string)` reason
+#[cfg(feature = "alloc")]
+pub fn encode_revert_reason(reason: &str) -> Vec<u8> {
+	// A string alone can't fail to encode, so it is encoded without a sink.
+	let len = 4 + 64 + (reason.len() + 31) / 32 * 32;
+	let mut payload = Vec::with_capacity(len);
+	payload.extend_from_slice(&util::pad_u32(ERROR_REASON_SELECTOR)[28..]);
+	payload.extend_from_slice(&util::pad_u32(32));
+	payload.extend_from_slice(&util::pad_u32(reason.len() as u32));
+	payload.extend_from_slice(reason.as_bytes());
+	payload.resize(len, 0);
+	payload
+}
+
+/// Encode revert payload of the Solidity custom error with `selector`
+/// The fields of `error` are encoded in the same way as the arguments of a call
+/// Fails if the `AbiEncode` implementation of the error is inconsistent
+#[cfg(feature = "alloc")]
+pub fn encode_custom_error<T: AbiEncode + ?Sized>(selector: u32, error: &T) -> Result<Vec<u8>, SinkError> {
+	let mut sink = Sink::with_selector(selector);
+	error.encode(&mut sink);
+	sink.finalize()
+}
+
+/// Abort the execution with the `Error(string)` reason of a dispatch error
+///
+/// The payload is encoded on the stack, which fits descriptions of up to 64 bytes.
+pub(crate) fn revert_with_description(description: &'static str) -> ! {
+	let mut payload = [0u8; 4 + 4 * 32];
+	payload[0] = (ERROR_REASON_SELECTOR >> 24) as u8;
+	payload[1] = (ERROR_REASON_SELECTOR >> 16) as u8;
+	payload[2] = (ERROR_REASON_SELECTOR >> 8) as u8;
+	payload[3] = ERROR_REASON_SELECTOR as u8;
+	let len = {
+		let mut sink = Sink::from_slice(&mut payload[4..]);
+		sink.push(description);
+		// Longer descriptions are left out rather than cut off.
+		sink.finish().unwrap_or(0)
+	};
+	revert(&payload[..4 + len])
+}
+
+/// Revert payload the execution unwinds with, see `revert`
+#[cfg(feature = "std")]
+#[derive(Debug, PartialEq, Eq)]
+pub struct Revert(pub Vec<u8>);
+
+/// Abort the execution of the contract with the revert `payload`
+///
+/// The payload is passed to the host by its `panic` import, which fails the call.
+/// With `std` the execution unwinds with the payload as `Revert` instead.
+#[cfg(not(feature = "std"))]
+pub fn revert(payload: &[u8]) -> ! {
+	extern "C" {
+		fn panic(payload_ptr: *const u8, payload_len: u32) -> !;
+	}
+	unsafe { panic(payload.as_ptr(), payload.len() as u32) }
+}
+
+/// Abort the execution of the contract with the revert `payload`
+///
+/// The payload is passed to the host by its `panic` import, which fails the call.
+/// With `std` the execution unwinds with the payload as `Revert` instead.
+#[cfg(feature = "std")]
+pub fn revert(payload: &[u8]) -> ! {
+	::std::panic::resume_unwind(Box::new(Revert(payload.to_vec())))
+}
+
+/// Error of a call to another contract
+#[cfg(feature = "alloc")]
+#[derive(Debug, PartialEq, Eq)]
+pub enum CallError<E = ()> {
+	/// Call reverted with a Solidity `Error(string)` reason
+	Reason(String),
+	/// Call reverted with the custom error `E` of the method
+	Custom(E),
+	/// Call failed with unrecognized revert data, see `CallError::from_revert`
+	Revert(Vec<u8>),
+	/// Arguments of the call failed to encode
+	Encode(SinkError),
+	/// Output of the successful call failed to decode
+	Decode(Error),
+	/// Output of the successful call does not fit into the output buffer
+	/// of the given size, which is the return capacity of the generated client
+	Truncated(usize),
+}
+
+#[cfg(feature = "alloc")]
+impl<E> CallError<E> {
+	/// Error from the revert payload of a failed call
+	/// in the zero-initialized `payload` buffer
+	///
+	/// The host doesn't report the length of the revert data, so unrecognized
+	/// data is trimmed to the selector and the 32-byte words up to the last
+	/// non-zero byte of the buffer. Data of zeros only is trimmed to nothing.
+	pub fn from_revert(payload: &[u8]) -> Self {
+		if read_selector(payload) == Some(ERROR_REASON_SELECTOR) {
+			if let Ok(reason) = Stream::new(&payload[4..]).pop::<String>() {
+				return CallError::Reason(reason);
+			}
+		}
+		let len = match payload.iter().rposition(|&byte| byte != 0) {
+			Some(last) if last < 4 => 4,
+			Some(last) => 4 + (last - 4) / 32 * 32 + 32,
+			None => 0,
+		};
+		CallError::Revert(payload[..cmp::min(len, payload.len())].to_vec())
+	}
+
+	/// Error from the failed decoding of the output of a successful call
+	/// copied into the `output` buffer of the given `capacity`
+	///
+	/// The host copies only as much of the output as fits into the buffer
+	/// and doesn't report the length of the output. Decoding past the end of
+	/// a buffer the call filled up to its capacity is taken to mean that the
+	/// output was truncated, whatever the bytes at its end are. Otherwise the
+	/// output itself is malformed.
+	pub fn from_output(error: Error, output: &[u8], capacity: usize) -> Self {
+		match error.kind() {
+			ErrorKind::UnexpectedEof | ErrorKind::InvalidOffset if output.len() >= capacity => {
+				CallError::Truncated(capacity)
+			},
+			_ => CallError::Decode(error),
+		}
+	}
+
+	/// Reason of the revert, or the description of any other failure of the call
+	/// Used by the clients of methods returning `Result<T, String>`
+	pub fn into_reason(self) -> String {
+		match self {
+			CallError::Reason(reason) => reason,
+			other => format!("{}", other),
+		}
+	}
+
+	/// Reason of the revert, or the description of any other failure of the call,
+	/// which is leaked to live for the rest of the execution
+	/// Used by the clients of methods returning `Result<T, &str>`
+	pub fn into_static_reason(self) -> &'static str {
+		Box::leak(self.into_reason().into_boxed_str())
+	}
+}
+
+#[cfg(feature = "alloc")]
+impl<E> fmt::Display for CallError<E> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			CallError::Reason(ref reason) => write!(f, "call reverted: {}", reason),
+			CallError::Custom(_) => f.write_str("call reverted with custom error"),
+			CallError::Revert(ref data) => write!(f, "call reverted with {} bytes of data", data.len()),
+			CallError::Encode(ref error) => write!(f, "call arguments failed to encode: {}", error),
+			CallError::Decode(ref error) => write!(f, "call output failed to decode: {}", error),
+			CallError::Truncated(capacity) => write!(f, "call output exceeds the return capacity of {} bytes", capacity),
+		}
+	}
+}
+
+#[cfg(feature = "alloc")]
+impl<E: for<'a> AbiDecode<'a>> CallError<E> {
+	/// Error from the revert payload of a failed call to a method
+	/// reverting with the custom error `E` identified by `selector`
+	pub fn from_custom_revert(selector: u32, payload: &[u8]) -> Self {
+		if read_selector(payload) == Some(selector) {
+			if let Ok(error) = E::decode(&mut Stream::new(&payload[4..])) {
+				return CallError::Custom(error);
+			}
+		}
+		Self::from_revert(payload)
+	}
+}
@@ -1,69 +1,396 @@
 //! Sink module;
 
 use lib::*;
-use super::AbiType;
+use super::{util, AbiEncode};
+
+/// Error for encoding values into a sink
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SinkError {
+	/// Encoded values don't fit the fixed buffer of the sink
+	Overflow {
+		/// Number of bytes the encoding takes
+		required: usize,
+		/// Size of the buffer
+		capacity: usize,
+	},
+	/// Heads of the pushed values exceed the reserved heads
+	HeadOverflow {
+		/// Number of bytes reserved for the heads
+		reserved: usize,
+	},
+	/// Pushed values don't fill the reserved heads
+	Underflow {
+		/// Number of bytes pushed to the heads
+		pushed: usize,
+		/// Number of bytes reserved for the heads
+		reserved: usize,
+	},
+	/// Heads were written after a dynamic value, so the offset
+	/// of the dynamic value would point into the heads
+	InconsistentOffset {
+		/// Position of the misplaced head in the encoding
+		position: usize,
+	},
+}
+
+impl SinkError {
+	/// Static description of the error
+	pub fn description(&self) -> &'static str {
+		match *self {
+			SinkError::Overflow { .. } => "encoding exceeds the buffer",
+			SinkError::HeadOverflow { .. } => "overflow of the reserved heads",
+			SinkError::Underflow { .. } => "underflow of the reserved heads",
+			SinkError::InconsistentOffset { .. } => "heads written after a dynamic value",
+		}
+	}
+}
+
+impl fmt::Display for SinkError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			SinkError::Overflow { required, capacity } => {
+				write!(f, "{}: {} bytes required, {} available", self.description(), required, capacity)
+			},
+			SinkError::HeadOverflow { reserved } => {
+				write!(f, "{}: more than {} bytes pushed", self.description(), reserved)
+			},
+			SinkError::Underflow { pushed, reserved } => {
+				write!(f, "{}: {}/{} bytes pushed", self.description(), pushed, reserved)
+			},
+			SinkError::InconsistentOffset { position } => {
+				write!(f, "{} at byte {}", self.description(), position)
+			},
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for SinkError {
+	fn description(&self) -> &str {
+		SinkError::description(self)
+	}
+}
+
+/// Buffer the values are encoded into
+enum Buffer<'a> {
+	/// Buffer on the heap growing as needed
+	#[cfg(feature = "alloc")]
+	Heap(Vec<u8>),
+	/// Fixed buffer provided by the caller and the length of the encoding,
+	/// bytes beyond the end of the buffer are dropped
+	Fixed(&'a mut [u8], usize),
+}
+
+impl<'a> Buffer<'a> {
+	fn len(&self) -> usize {
+		match *self {
+			#[cfg(feature = "alloc")]
+			Buffer::Heap(ref vec) => vec.len(),
+			Buffer::Fixed(_, len) => len,
+		}
+	}
+
+	/// Extend the buffer with zeros to `new_len` bytes
+	fn grow(&mut self, new_len: usize) {
+		match *self {
+			#[cfg(feature = "alloc")]
+			Buffer::Heap(ref mut vec) => vec.resize(new_len, 0),
+			Buffer::Fixed(ref mut slice, ref mut len) => {
+				let capacity = slice.len();
+				for byte in slice[cmp::min(*len, capacity)..cmp::min(new_len, capacity)].iter_mut() {
+					*byte = 0;
+				}
+				*len = new_len;
+			},
+		}
+	}
+
+	/// Overwrite the bytes at `position`, which have to be within the buffer
+	fn write_at(&mut self, position: usize, bytes: &[u8]) {
+		let end = position + bytes.len();
+		match *self {
+			#[cfg(feature = "alloc")]
+			Buffer::Heap(ref mut vec) => vec[position..end].copy_from_slice(bytes),
+			Buffer::Fixed(ref mut slice, _) => {
+				let capacity = slice.len();
+				if position < capacity {
+					let end = cmp::min(end, capacity);
+					slice[position..end].copy_from_slice(&bytes[..end - position]);
+				}
+			},
+		}
+	}
+
+	fn extend(&mut self, bytes: &[u8]) {
+		match *self {
+			#[cfg(feature = "alloc")]
+			Buffer::Heap(ref mut vec) => vec.extend_from_slice(bytes),
+			Buffer::Fixed(..) => {
+				let position = self.len();
+				self.grow(position + bytes.len());
+				self.write_at(position, bytes);
+			},
+		}
+	}
+
+	/// Number of bytes written, failing if they don't fit the buffer
+	fn written(&self) -> Result<usize, SinkError> {
+		match *self {
+			#[cfg(feature = "alloc")]
+			Buffer::Heap(ref vec) => Ok(vec.len()),
+			Buffer::Fixed(ref slice, len) if len > slice.len() => {
+				Err(SinkError::Overflow { required: len, capacity: slice.len() })
+			},
+			Buffer::Fixed(_, len) => Ok(len),
+		}
+	}
+}
 
 /// Sink for returning number of arguments
-pub struct Sink {
-	capacity: usize,
-	preamble: Vec<u8>,
-	heap: Vec<u8>,
+///
+/// Values are encoded into a single buffer in the order they are pushed.
+/// The heads of a sequence of values are reserved up front, so every dynamic
+/// value is written once right to the end of the buffer.
+///
+/// The buffer is a `Vec` growing as needed, or a fixed slice provided by
+/// `from_slice`, which never allocates. Without the `alloc` feature only
+/// fixed buffers are available, so encoding doesn't need an allocator.
+///
+/// Pushing values in a way that can't be encoded consistently stops the
+/// encoding and is reported by `finalize` (or `finish`).
+pub struct Sink<'a> {
+	buffer: Buffer<'a>,
+	/// Start of the current sequence, offsets of dynamic values are relative to it.
+	start: usize,
+	/// Position of the next head of the current sequence.
+	head: usize,
+	/// End of the reserved heads of the current sequence,
+	/// `None` while heads are appended to the buffer.
+	head_end: Option<usize>,
+	/// First error of the encoding, values pushed after it are dropped.
+	error: Option<SinkError>,
+}
+
+#[cfg(feature = "alloc")]
+impl Default for Sink<'static> {
+	fn default() -> Self {
+		Sink::new()
+	}
+}
+
+#[cfg(feature = "alloc")]
+impl Sink<'static> {
+	/// New sink appending heads as they are pushed
+	///
+	/// Only the last pushed value may be dynamic unless the heads
+	/// are reserved by `reserve` first.
+	pub fn new() -> Self {
+		Sink {
+			buffer: Buffer::Heap(Vec::new()),
+			start: 0,
+			head: 0,
+			head_end: None,
+			error: None,
+		}
+	}
+
+	/// New sink with `head_size` bytes reserved for the heads of the pushed values
+	pub fn with_head_size(head_size: usize) -> Self {
+		let mut sink = Sink::new();
+		sink.reserve(head_size);
+		sink
+	}
+
+	/// New sink starting with the 4 byte `selector`, which the offsets don't account for
+	pub fn with_selector(selector: u32) -> Self {
+		Sink {
+			buffer: Buffer::Heap(vec![
+				(selector >> 24) as u8,
+				(selector >> 16) as u8,
+				(selector >> 8) as u8,
+				selector as u8,
+			]),
+			start: 4,
+			head: 4,
+			head_end: None,
+			error: None,
+		}
+	}
 }
 
-impl Sink {
-	/// New sink with known capacity
-	pub fn new(capacity: usize) -> Self {
+impl<'a> Sink<'a> {
+	/// New sink writing into the fixed `buffer` without allocating
+	///
+	/// Encoding past the end of the buffer is reported by `finish` and `finalize`.
+	pub fn from_slice(buffer: &'a mut [u8]) -> Self {
 		Sink {
-			capacity: 32 * capacity,
-			preamble: Vec::with_capacity(32 * capacity),
-			heap: Vec::new(),
+			buffer: Buffer::Fixed(buffer, 0),
+			start: 0,
+			head: 0,
+			head_end: None,
+			error: None,
 		}
 	}
 
-	fn top_ptr(&self) -> usize {
-		self.preamble.capacity() + self.heap.len()
+	/// Reserve `head_size` bytes for the heads of the values pushed next
+	/// Heads can't be reserved if they are already reserved or followed by dynamic values
+	pub fn reserve(&mut self, head_size: usize) {
+		if self.error.is_some() {
+			return;
+		}
+		if self.head_end.is_some() || self.head != self.buffer.len() {
+			return self.fail(SinkError::InconsistentOffset { position: self.head });
+		}
+		self.buffer.grow(self.head + head_size);
+		self.head_end = Some(self.head + head_size);
 	}
 
 	/// Consume `val` to the Sink
-	pub fn push<T: AbiType>(&mut self, val: T) {
+	pub fn push<T: AbiEncode>(&mut self, val: T) {
+		self.push_ref(&val)
+	}
+
+	/// Push `val` to the Sink by reference, which also allows slices and `str`
+	pub fn push_ref<T: AbiEncode + ?Sized>(&mut self, val: &T) {
 		if T::IS_FIXED {
 			val.encode(self)
 		} else {
-			let mut nested_sink = Sink::new(1);
-			val.encode(&mut nested_sink);
-			let top_ptr = self.top_ptr() as u32;
-			nested_sink.drain_to(&mut self.heap);
-			self.push(top_ptr);
+			let slot = self.head;
+			self.write(&[0u8; 32]);
+			if self.error.is_some() {
+				return;
+			}
+			// Dynamic value is written to the end of the buffer
+			// as a sequence of its own.
+			let offset = self.buffer.len() - self.start;
+			self.buffer.write_at(slot, &util::pad_u32(offset as u32));
+			let (start, head, head_end) = (self.start, self.head, self.head_end);
+			self.start = self.buffer.len();
+			self.head = self.start;
+			self.head_end = None;
+			val.encode(self);
+			self.start = start;
+			self.head = head;
+			self.head_end = head_end;
 		}
 	}
 
-	/// Drain current Sink to the target vector
-	pub fn drain_to(self, target: &mut Vec<u8>) {
-		let preamble = self.preamble;
-		let heap = self.heap;
-		target.reserve(preamble.len() + heap.len());
-		target.extend_from_slice(&preamble);
-		target.extend_from_slice(&heap);
+	/// Encode a sequence of values like a tuple by `encode`, reserving `head_size` bytes for their heads
+	/// Offsets of dynamic values in the sequence are relative to the start of its heads
+	pub fn frame<F: FnOnce(&mut Sink<'a>)>(&mut self, head_size: usize, encode: F) {
+		let (start, head_end) = (self.start, self.head_end);
+		if self.error.is_some() {
+			return;
+		}
+		if head_end.is_some() {
+			// Sequence has to be encoded after the reserved heads.
+			return self.fail(SinkError::InconsistentOffset { position: self.head });
+		}
+		self.start = self.head;
+		self.reserve(head_size);
+		encode(self);
+		if self.error.is_none() {
+			self.check_underflow();
+		}
+		// The sequence is part of the heads appended so far.
+		self.start = start;
+		self.head = self.buffer.len();
+		self.head_end = head_end;
+	}
+
+	/// Write `bytes` as the next head
+	/// Fails if the bytes don't fit the reserved heads
+	pub fn write(&mut self, bytes: &[u8]) {
+		if self.error.is_some() {
+			return;
+		}
+		let end = self.head + bytes.len();
+		match self.head_end {
+			Some(head_end) => {
+				if end > head_end {
+					return self.fail(SinkError::HeadOverflow { reserved: head_end - self.start });
+				}
+				self.buffer.write_at(self.head, bytes);
+			},
+			None => {
+				// Heads have to be reserved before pushing dynamic values.
+				if self.head != self.buffer.len() {
+					return self.fail(SinkError::InconsistentOffset { position: self.head });
+				}
+				self.buffer.extend(bytes);
+			},
+		}
+		self.head = end;
+	}
+
+	/// Write `bytes` as the next head, padded with zeros to the next 32 byte step
+	pub fn write_padded(&mut self, bytes: &[u8]) {
+		self.write(bytes);
+		let padding = (32 - bytes.len() % 32) % 32;
+		self.write(&[0u8; 32][..padding]);
+	}
+
+	/// Number of bytes encoded so far, including those beyond the end of a fixed buffer
+	pub fn len(&self) -> usize {
+		self.buffer.len()
+	}
+
+	/// Whether nothing is encoded yet
+	pub fn is_empty(&self) -> bool {
+		self.buffer.len() == 0
+	}
+
+	/// Consume current Sink, returning the number of bytes written to its buffer
+	/// Fails if the values were not encoded consistently or don't fit a fixed buffer.
+	///
+	/// Unlike `finalize` it never allocates, the encoding of a fixed buffer
+	/// is in the bytes of the buffer up to the returned length.
+	pub fn finish(mut self) -> Result<usize, SinkError> {
+		self.check_underflow();
+		match self.error {
+			Some(error) => Err(error),
+			None => self.buffer.written(),
+		}
 	}
 
 	/// Consume current Sink to produce a vector with content.
-	/// May panic if declared number of arguments does not match the resulting number of bytes should be produced.
-	pub fn finalize_panicking(self) -> Vec<u8> {
-		if self.preamble.len() != self.capacity { panic!("Underflow of pushed parameters {}/{}!", self.preamble.len(), self.capacity); }
-		let mut result = self.preamble;
-		let heap = self.heap;
+	/// Fails if the values were not encoded consistently or don't fit a fixed buffer.
+	#[cfg(feature = "alloc")]
+	pub fn finalize(mut self) -> Result<Vec<u8>, SinkError> {
+		self.check_underflow();
+		if let Some(error) = self.error {
+			return Err(error);
+		}
+		self.buffer.written()?;
+		match self.buffer {
+			Buffer::Heap(vec) => Ok(vec),
+			Buffer::Fixed(slice, len) => Ok(slice[..len].to_vec()),
+		}
+	}
 
-		result.extend_from_slice(&heap);
-		result
+	/// Consume current Sink to produce a vector with content.
+	/// Panics if the values were not encoded consistently or don't fit a fixed buffer.
+	#[cfg(feature = "alloc")]
+	#[deprecated(since = "0.3.0", note = "use `finalize`, which returns the error instead of panicking")]
+	pub fn finalize_panicking(self) -> Vec<u8> {
+		match self.finalize() {
+			Ok(payload) => payload,
+			Err(error) => panic!("Invalid encoding: {}", error),
+		}
 	}
 
-	/// Mutable reference to the Sink preamble
-	pub fn preamble_mut(&mut self) -> &mut Vec<u8> {
-		&mut self.preamble
+	fn check_underflow(&mut self) {
+		if let Some(head_end) = self.head_end {
+			if self.head < head_end {
+				let (pushed, reserved) = (self.head - self.start, head_end - self.start);
+				self.fail(SinkError::Underflow { pushed: pushed, reserved: reserved });
+			}
+		}
 	}
 
-	/// Mutable reference to the Sink heap
-	pub fn heap_mut(&mut self) -> &mut Vec<u8> {
-		&mut self.heap
+	fn fail(&mut self, error: SinkError) {
+		if self.error.is_none() {
+			self.error = Some(error);
+		}
 	}
 }
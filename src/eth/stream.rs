@@ -1,12 +1,21 @@
 //! Stream module
 
 use lib::*;
-use super::{AbiType, Error};
+use super::{AbiDecode, Error, ErrorKind};
 
 /// Stream interpretation of incoming payload
+#[derive(Clone)]
 pub struct Stream<'a> {
 	payload: &'a [u8],
 	position: usize,
+	/// Position of the payload within the payload of the outermost stream.
+	base: usize,
+	strict: bool,
+	/// Start of the first and end of the last dynamic value decoded so far.
+	tail: Option<(usize, usize)>,
+	/// Number of words the decoded values may still allocate,
+	/// shared with the nested streams.
+	budget: usize,
 }
 
 impl<'a> Stream<'a> {
@@ -16,18 +25,114 @@ impl<'a> Stream<'a> {
 		Stream {
 			payload: raw,
 			position: 0,
+			base: 0,
+			strict: false,
+			tail: None,
+			budget: raw.len() / 32,
 		}
 	}
 
+	/// New stream for known payload that only accepts the canonical encoding
+	///
+	/// Non-zero padding, offsets out of order or pointing into the head
+	/// and trailing bytes (checked by `finish`) are rejected.
+	pub fn new_strict(raw: &'a [u8]) -> Self {
+		Stream {
+			strict: true,
+			..Stream::new(raw)
+		}
+	}
+
+	/// Whether stream only accepts the canonical encoding
+	pub fn is_strict(&self) -> bool { self.strict }
+
 	/// Pop next argument of known type
-	pub fn pop<T: AbiType>(&mut self) -> Result<T, Error> {
-		if T::IS_FIXED {
+	pub fn pop<T: AbiDecode<'a>>(&mut self) -> Result<T, Error> {
+		let position = self.base + self.position;
+		let result = if T::IS_FIXED {
 			T::decode(self)
 		} else {
-			let offset = u32::decode(self)?;
-			let mut nested_stream = Stream::new(&self.payload[offset as usize..]);
-			T::decode(&mut nested_stream)
+			self.pop_dynamic()
+		};
+		result.map_err(|error| error.locate::<T>(position))
+	}
+
+	fn pop_dynamic<T: AbiDecode<'a>>(&mut self) -> Result<T, Error> {
+		let offset = u32::decode(self)? as usize;
+		if self.strict {
+			// Dynamic values follow the head in the order of their offsets.
+			let is_canonical = match self.tail {
+				Some((_, end)) => offset == end,
+				None => offset >= self.position,
+			};
+			if !is_canonical {
+				return Err(ErrorKind::NonCanonicalOffset.into());
+			}
+		}
+		let mut nested_stream = self.nested(offset)?;
+		let result = T::decode(&mut nested_stream);
+		self.budget = nested_stream.budget;
+		let result = result.map_err(|error| error.locate::<T>(nested_stream.base))?;
+		let end = offset + nested_stream.end()?;
+		self.tail = Some((self.tail.map_or(offset, |(start, _)| start), end));
+		Ok(result)
+	}
+
+	/// Stream of the payload starting at `offset` in the same mode
+	pub fn nested(&self, offset: usize) -> Result<Stream<'a>, Error> {
+		if offset > self.payload.len() {
+			return Err(ErrorKind::InvalidOffset.into());
+		}
+		let payload: &'a [u8] = self.payload;
+		Ok(Stream {
+			payload: &payload[offset..],
+			position: 0,
+			base: self.base + offset,
+			strict: self.strict,
+			tail: None,
+			budget: self.budget,
+		})
+	}
+
+	/// Advance past the values decoded by the `nested` stream starting at the
+	/// current position, taking over the budget it left
+	pub fn skip_nested(&mut self, nested: &Stream<'a>) -> Result<(), Error> {
+		self.advance(nested.end()?)?;
+		self.budget = nested.budget;
+		Ok(())
+	}
+
+	/// Charge `words` to the budget of the values the stream may allocate
+	///
+	/// Canonical payloads back every allocated element (or 32 bytes of `bytes`
+	/// and `string`) by a word of their own, so the values allocate at most as
+	/// many words as the payload has. Offsets pointing to the same value
+	/// several times could otherwise make a small payload allocate without bound.
+	pub fn charge(&mut self, words: usize) -> Result<(), Error> {
+		if words > self.budget {
+			return Err(ErrorKind::UnexpectedEof.into());
 		}
+		self.budget -= words;
+		Ok(())
+	}
+
+	/// End of the decoded data, including dynamic values
+	pub fn end(&self) -> Result<usize, Error> {
+		match self.tail {
+			// The first dynamic value has to follow the head immediately.
+			Some((start, _)) if self.strict && start != self.position => Err(ErrorKind::NonCanonicalOffset.into()),
+			Some((_, end)) => Ok(cmp::min(cmp::max(end, self.position), self.payload.len())),
+			None => Ok(cmp::min(self.position, self.payload.len())),
+		}
+	}
+
+	/// Finish decoding, rejecting trailing bytes in strict mode
+	pub fn finish(&self) -> Result<(), Error> {
+		let end = self.end()?;
+		if self.strict && end != self.payload.len() {
+			return Err(ErrorKind::TrailingBytes.into());
+		}
+		Ok(())
 	}
 
 	/// Current position for the stream
@@ -35,18 +140,44 @@ impl<'a> Stream<'a> {
 
 	/// Advance stream position for `amount` bytes
 	pub fn advance(&mut self, amount: usize) -> Result<usize, Error> {
-		if self.position + amount > self.payload.len() {
-			return Err(Error::UnexpectedEof);
+		let new_position = self.position.checked_add(amount).ok_or(ErrorKind::UnexpectedEof)?;
+		if new_position > self.payload.len() {
+			return Err(ErrorKind::UnexpectedEof.into());
 		}
 
 		let old_position = self.position;
-		self.position += amount;
+		self.position = new_position;
 		Ok(old_position)
 	}
 
+	/// Advance stream position for `amount` bytes, returning the bytes advanced over
+	pub fn read(&mut self, amount: usize) -> Result<&'a [u8], Error> {
+		let previous_position = self.advance(amount)?;
+		let payload: &'a [u8] = self.payload;
+		Ok(&payload[previous_position..self.position])
+	}
+
+	/// Advance stream position for `amount` bytes and the padding to the next 32 byte step,
+	/// returning the bytes advanced over without padding
+	/// In strict mode the padding has to be present and zero
+	pub fn read_padded(&mut self, amount: usize) -> Result<&'a [u8], Error> {
+		let result = self.read(amount)?;
+		if self.strict {
+			let padding = self.read((32 - amount % 32) % 32)?;
+			if !padding.iter().all(|x| *x == 0) {
+				return Err(ErrorKind::NonZeroPadding.into());
+			}
+		} else {
+			self.finish_advance();
+		}
+		Ok(result)
+	}
+
 	/// Finish current advance, advancing stream to the next 32 byte step
 	pub fn finish_advance(&mut self) {
-		if self.position % 32 > 0 { self.position += 32 - (self.position % 32); }
+		if self.position % 32 > 0 {
+			self.position = self.position.saturating_add(32 - (self.position % 32));
+		}
 	}
 
 	/// Stream payload
@@ -55,7 +186,12 @@ impl<'a> Stream<'a> {
 	}
 
 	/// Peek next byte in stream
-	pub fn peek(&self) -> u8 {
-		self.payload[self.position]
+	pub fn peek(&self) -> Result<u8, Error> {
+		self.payload.get(self.position).cloned().ok_or(ErrorKind::UnexpectedEof.into())
+	}
+
+	/// Number of bytes left in stream
+	pub fn remaining(&self) -> usize {
+		self.payload.len().saturating_sub(self.position)
 	}
 }
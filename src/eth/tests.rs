@@ -5,6 +5,7 @@ use lib::*;
 #[cfg(feature = "std")]
 mod hextest {
 	use super::super::*;
+	use super::super::types::*;
 	use lib::*;
 
 	#[test]
@@ -53,6 +54,1086 @@ mod hextest {
 		);
 	}
 
+	#[test]
+	fn tuple_fixed() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000045
+			0000000000000000000000000000000000000000000000000000000000000001
+		");
+
+		let mut sink = Sink::new();
+		sink.push((69u32, true));
+		assert_eq!(sink.finalize().unwrap(), encoded.to_vec());
+		assert_eq!(super::single_decode::<(u32, bool)>(&encoded), (69u32, true));
+	}
+
+	#[test]
+	fn tuple_dynamic() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000002
+			1234000000000000000000000000000000000000000000000000000000000000
+		");
+		let value = (U256::from(1), vec![0x12u8, 0x34]);
+
+		assert_eq!(super::single_encode(value.clone()), encoded.to_vec());
+		assert_eq!(super::single_decode::<(U256, Vec<u8>)>(&encoded), value);
+	}
+
+	#[test]
+	fn tuple_nested_dynamic() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000045
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000003
+			6162630000000000000000000000000000000000000000000000000000000000
+		");
+		let value = (true, String::from("abc"));
+
+		let mut sink = Sink::new();
+		sink.push(69u32);
+		sink.push(value.clone());
+		assert_eq!(sink.finalize().unwrap(), encoded.to_vec());
+
+		let mut stream = Stream::new(&encoded);
+		assert_eq!(stream.pop::<u32>().unwrap(), 69);
+		assert_eq!(stream.pop::<(bool, String)>().unwrap(), value);
+	}
+
+	#[test]
+	fn fixed_array_static() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000045
+		");
+		let value = [U256::from(1), U256::from(2)];
+
+		let mut sink = Sink::new();
+		sink.push(value);
+		sink.push(69u32);
+		assert_eq!(sink.finalize().unwrap(), encoded.to_vec());
+
+		let mut stream = Stream::new(&encoded);
+		assert_eq!(stream.pop::<[U256; 2]>().unwrap(), value);
+		assert_eq!(stream.pop::<u32>().unwrap(), 69);
+	}
+
+	#[test]
+	fn fixed_array_dynamic() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000045
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000080
+			0000000000000000000000000000000000000000000000000000000000000001
+			6100000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000001
+			6200000000000000000000000000000000000000000000000000000000000000
+		");
+		let value = [String::from("a"), String::from("b")];
+
+		let mut sink = Sink::new();
+		sink.push(69u32);
+		sink.push(value.clone());
+		assert_eq!(sink.finalize().unwrap(), encoded.to_vec());
+
+		let mut stream = Stream::new(&encoded);
+		assert_eq!(stream.pop::<u32>().unwrap(), 69);
+		assert_eq!(stream.pop::<[String; 2]>().unwrap(), value);
+	}
+
+	#[test]
+	fn revert_reason() {
+		let encoded = hex!("
+			08c379a0
+			0000000000000000000000000000000000000000000000000000000000000020
+			000000000000000000000000000000000000000000000000000000000000001a
+			4e6f7420656e6f7567682045746865722070726f76696465642e000000000000
+		");
+
+		assert_eq!(encode_revert_reason("Not enough Ether provided."), encoded.to_vec());
+		assert_eq!(DispatchError::Revert(encoded.to_vec()).into_revert_payload(), encoded.to_vec());
+	}
+
+	struct InvalidEndpoint;
+
+	impl EndpointInterface for InvalidEndpoint {
+		fn try_dispatch(&mut self, _payload: &[u8]) -> Result<Vec<u8>, DispatchError> {
+			Err(DispatchError::InvalidSelector)
+		}
+
+		fn try_dispatch_ctor(&mut self, _payload: &[u8]) -> Result<(), DispatchError> {
+			Err(DispatchError::ShortPayload)
+		}
+	}
+
+	#[test]
+	fn dispatch_revert() {
+		use std::panic::{self, AssertUnwindSafe};
+
+		let encoded = hex!("
+			08c379a0
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000018
+			496e76616c6964206d6574686f64207369676e61747572650000000000000000
+		");
+		let revert = panic::catch_unwind(AssertUnwindSafe(|| InvalidEndpoint.dispatch(&[])))
+			.unwrap_err();
+		assert_eq!(revert.downcast_ref::<Revert>(), Some(&Revert(encoded.to_vec())));
+
+		let revert = panic::catch_unwind(AssertUnwindSafe(|| InvalidEndpoint.dispatch_ctor(&[])))
+			.unwrap_err();
+		assert_eq!(revert.downcast_ref::<Revert>(), Some(&Revert(encode_revert_reason("Invalid abi invoke"))));
+	}
+
+	#[test]
+	fn call_output_truncated() {
+		// `bytes` of 64 bytes copied into a buffer of 96 bytes
+		let output = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000040
+			0707070707070707070707070707070707070707070707070707070707070707
+		");
+		let error = Stream::new(&output).pop::<Vec<u8>>().unwrap_err();
+		assert_eq!(CallError::<()>::from_output(error, &output, 96), CallError::Truncated(96));
+
+		// `uint256[]` of zeros copied into a buffer of 96 bytes ends in zeros
+		let output = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000003
+			0000000000000000000000000000000000000000000000000000000000000000
+		");
+		let error = Stream::new(&output).pop::<Vec<U256>>().unwrap_err();
+		assert_eq!(CallError::<()>::from_output(error, &output, 96), CallError::Truncated(96));
+
+		// `bytes` of 64 zero bytes copied into a buffer of 96 bytes
+		let output = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000000
+		");
+		let error = Stream::new(&output).pop::<Vec<u8>>().unwrap_err();
+		assert_eq!(CallError::<()>::from_output(error, &output, 96), CallError::Truncated(96));
+	}
+
+	#[test]
+	fn call_output_malformed() {
+		// Offset pointing past the end of output shorter than the buffer
+		let output = hex!("
+			0000000000000000000000000000000000000000000000000000000000001000
+			0000000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000000
+		");
+		let error = Stream::new(&output).pop::<Vec<u8>>().unwrap_err();
+		assert_eq!(error.kind(), ErrorKind::InvalidOffset);
+		assert_eq!(CallError::<()>::from_output(error.clone(), &output, 1024), CallError::Decode(error));
+
+		// Invalid output is malformed even if it fills the buffer
+		let output = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000001
+			ff00000000000000000000000000000000000000000000000000000000000000
+		");
+		let error = Stream::new(&output).pop::<String>().unwrap_err();
+		assert_eq!(error.kind(), ErrorKind::InvalidUtf8);
+		assert_eq!(CallError::<()>::from_output(error.clone(), &output, 96), CallError::Decode(error));
+	}
+
+	#[test]
+	fn call_revert_trimmed() {
+		// Custom error with a single word copied into a buffer of 100 bytes
+		let mut output = vec![0u8; 100];
+		output[..36].copy_from_slice(&hex!("
+			cf479181
+			0000000000000000000000000000000000000000000000000000000000000100
+		"));
+		assert_eq!(CallError::<()>::from_revert(&output), CallError::Revert(output[..36].to_vec()));
+		assert_eq!(CallError::<()>::from_revert(&output[..35]), CallError::Revert(output[..35].to_vec()));
+		assert_eq!(CallError::<()>::from_revert(&[0u8; 32]), CallError::Revert(Vec::new()));
+	}
+
+	#[test]
+	fn malicious_bytes_length() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			00000000000000000000000000000000000000000000000000000000ffffffff
+			1234000000000000000000000000000000000000000000000000000000000000
+		");
+		assert_eq!(Stream::new(&encoded).pop::<Vec<u8>>().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+		assert_eq!(Stream::new(&encoded).pop::<String>().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+	}
+
+	#[test]
+	fn malicious_array_length() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			00000000000000000000000000000000000000000000000000000000ffffffff
+			0000000000000000000000000000000000000000000000000000000000000001
+		");
+		assert_eq!(Stream::new(&encoded).pop::<Vec<U256>>().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+		assert_eq!(Stream::new(&encoded).pop::<Vec<String>>().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+	}
+
+	#[test]
+	fn malicious_offset() {
+		let encoded = hex!("
+			00000000000000000000000000000000000000000000000000000000ffffffe0
+		");
+		assert_eq!(Stream::new(&encoded).pop::<Vec<u8>>().unwrap_err().kind(), ErrorKind::InvalidOffset);
+
+		// offset to the very end of the payload leaves no room for the length
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+		");
+		assert_eq!(Stream::new(&encoded).pop::<String>().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+	}
+
+	#[test]
+	fn malicious_aliased_offsets() {
+		// all elements of the outer array point to the same inner array
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000004
+			0000000000000000000000000000000000000000000000000000000000000080
+			0000000000000000000000000000000000000000000000000000000000000080
+			0000000000000000000000000000000000000000000000000000000000000080
+			0000000000000000000000000000000000000000000000000000000000000080
+			0000000000000000000000000000000000000000000000000000000000000004
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000003
+			0000000000000000000000000000000000000000000000000000000000000004
+		");
+		let error = Stream::new(&encoded).pop::<Vec<Vec<U256>>>().unwrap_err();
+		assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+		assert_eq!(error.path(), &[PathSegment::Index(1)][..]);
+
+		// aliasing is accepted as long as the values allocate no more words than the payload has
+		assert_eq!(Stream::new(&encoded).pop::<Vec<Vec<u8>>>().unwrap(), vec![vec![0; 4]; 4]);
+
+		// lazily decoded elements don't allocate, so they may alias
+		let elements = Stream::new(&encoded).pop::<ArrayIter<ArrayIter<U256>>>().unwrap();
+		assert_eq!(elements.map(|element| element.unwrap().count()).collect::<Vec<_>>(), vec![4; 4]);
+	}
+
+	#[test]
+	fn truncated_payload() {
+		assert_eq!(Stream::new(&[]).pop::<i32>().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+		assert_eq!(Stream::new(&[0xff; 31]).pop::<i64>().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+		assert_eq!(Stream::new(&[0; 16]).pop::<[u8; 4]>().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+	}
+
+	#[test]
+	fn strict_padding() {
+		let encoded = hex!("
+			1234000000000000000000000000000000000000000000000000000000000001
+		");
+		assert_eq!(Stream::new(&encoded).pop::<[u8; 2]>().unwrap(), [0x12, 0x34]);
+		assert_eq!(Stream::new_strict(&encoded).pop::<[u8; 2]>().unwrap_err().kind(), ErrorKind::NonZeroPadding);
+
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000002
+			1234000000000000000000000000000000000000000000000000000000000001
+		");
+		assert_eq!(Stream::new(&encoded).pop::<Vec<u8>>().unwrap(), vec![0x12, 0x34]);
+		assert_eq!(Stream::new_strict(&encoded).pop::<Vec<u8>>().unwrap_err().kind(), ErrorKind::NonZeroPadding);
+
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000002
+			6869
+		");
+		assert_eq!(Stream::new(&encoded).pop::<String>().unwrap(), String::from("hi"));
+		assert_eq!(Stream::new_strict(&encoded).pop::<String>().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+
+		let encoded = hex!("
+			0000000000000000000000010101010101010101010101010101010101010101
+		");
+		assert_eq!(Stream::new(&encoded).pop::<Address>().unwrap(), Address::from([1u8; 20]));
+		assert_eq!(Stream::new_strict(&encoded).pop::<Address>().unwrap_err().kind(), ErrorKind::NonZeroPadding);
+	}
+
+	#[test]
+	fn strict_trailing_bytes() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000045
+			0000000000000000000000000000000000000000000000000000000000000000
+		");
+		let mut stream = Stream::new(&encoded);
+		assert_eq!(stream.pop::<u32>().unwrap(), 69);
+		assert_eq!(stream.finish(), Ok(()));
+
+		let mut stream = Stream::new_strict(&encoded);
+		assert_eq!(stream.pop::<u32>().unwrap(), 69);
+		assert_eq!(stream.finish().unwrap_err().kind(), ErrorKind::TrailingBytes);
+	}
+
+	#[test]
+	fn strict_offsets() {
+		// second offset points back into the head
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000000
+		");
+		let mut stream = Stream::new(&encoded);
+		assert_eq!(stream.pop::<Vec<u8>>().unwrap(), Vec::new());
+		assert_eq!(stream.pop::<Vec<u8>>().unwrap(), vec![0u8; 32]);
+
+		let mut stream = Stream::new_strict(&encoded);
+		assert_eq!(stream.pop::<Vec<u8>>().unwrap(), Vec::new());
+		assert_eq!(stream.pop::<Vec<u8>>().unwrap_err().kind(), ErrorKind::NonCanonicalOffset);
+
+		// values are shared by both offsets
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000000
+		");
+		let mut stream = Stream::new_strict(&encoded);
+		assert_eq!(stream.pop::<Vec<u8>>().unwrap(), Vec::new());
+		assert_eq!(stream.pop::<Vec<u8>>().unwrap_err().kind(), ErrorKind::NonCanonicalOffset);
+
+		// gap between the head and the first value
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000000
+		");
+		let mut stream = Stream::new(&encoded);
+		assert_eq!(stream.pop::<Vec<u8>>().unwrap(), Vec::new());
+		assert_eq!(stream.finish(), Ok(()));
+
+		let mut stream = Stream::new_strict(&encoded);
+		assert_eq!(stream.pop::<Vec<u8>>().unwrap(), Vec::new());
+		assert_eq!(stream.finish().unwrap_err().kind(), ErrorKind::NonCanonicalOffset);
+	}
+
+	#[test]
+	fn borrowed_encode() {
+		let numbers = vec![U256::from(1), U256::from(2)];
+		let note = String::from("abc");
+
+		let mut owned = Sink::with_head_size(96);
+		owned.push(numbers.clone());
+		owned.push(note.clone());
+		owned.push(vec![0x12u8, 0x34]);
+
+		let mut borrowed = Sink::with_head_size(96);
+		borrowed.push(&numbers);
+		borrowed.push("abc");
+		borrowed.push_ref(&[0x12u8, 0x34][..]);
+
+		assert_eq!(borrowed.finalize().unwrap(), owned.finalize().unwrap());
+		assert_eq!(super::single_encode((&note, &numbers[..])), super::single_encode((note, numbers)));
+	}
+
+	#[test]
+	fn borrowed_decode() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000080
+			0000000000000000000000000000000000000000000000000000000000000002
+			1234000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000003
+			6162630000000000000000000000000000000000000000000000000000000000
+		");
+		let mut stream = Stream::new_strict(&encoded);
+
+		let bytes: &[u8] = stream.pop().unwrap();
+		let string: &str = stream.pop().unwrap();
+
+		assert_eq!(bytes, &[0x12u8, 0x34][..]);
+		assert_eq!(bytes.as_ptr(), encoded[96..].as_ptr());
+		assert_eq!(string, "abc");
+		assert_eq!(string.as_ptr(), encoded[160..].as_ptr());
+		assert_eq!(stream.finish(), Ok(()));
+	}
+
+	#[test]
+	fn array_iter() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000003
+			0000000000000000000000000000000000000000000000000000000000000060
+			00000000000000000000000000000000000000000000000000000000000000a0
+			00000000000000000000000000000000000000000000000000000000000000e0
+			0000000000000000000000000000000000000000000000000000000000000001
+			6100000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000002
+			6263000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000000
+		");
+		let mut stream = Stream::new_strict(&encoded);
+
+		let mut strings: ArrayIter<&str> = stream.pop().unwrap();
+		assert_eq!(stream.finish(), Ok(()));
+
+		assert_eq!(strings.len(), 3);
+		assert_eq!(strings.next(), Some(Ok("a")));
+		assert_eq!(super::single_encode(strings.clone()), super::single_encode(vec!["bc", ""]));
+		assert_eq!(strings.collect::<Result<Vec<_>, _>>(), Ok(vec!["bc", ""]));
+
+		// elements are validated when the array is decoded, in both modes
+		let mut encoded = encoded;
+		encoded[319] = 0xff;
+		assert_eq!(
+			Stream::new_strict(&encoded).pop::<ArrayIter<&str>>().err().unwrap().path(),
+			&[PathSegment::Index(2)][..],
+		);
+		let error = Stream::new(&encoded).pop::<ArrayIter<&str>>().err().unwrap();
+		assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+		assert_eq!(error.path(), &[PathSegment::Index(2)][..]);
+	}
+
+	#[test]
+	fn packed() {
+		// example of the Solidity ABI specification
+		let value = (-1i16, [0x42u8], 0x03u16, "Hello, world!");
+		assert_eq!(encode_packed(&value), hex!("ffff42000348656c6c6f2c20776f726c6421").to_vec());
+
+		let mut address = [0u8; 20];
+		address[19] = 0x45;
+		let value = (Address::from(address), U256::from(1), vec![0x12u8, 0x34]);
+		assert_eq!(encode_packed(&value), hex!("
+			0000000000000000000000000000000000000045
+			0000000000000000000000000000000000000000000000000000000000000001
+			1234
+		").to_vec());
+	}
+
+	#[test]
+	fn packed_arrays() {
+		// elements are padded to 32 bytes, `bytesN` to the right
+		let value = (vec![1u16, 2], [[0x12u8, 0x34]], vec![true]);
+		assert_eq!(encode_packed(&value), hex!("
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000002
+			1234000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000001
+		").to_vec());
+
+		// `bytes` and `string` to a multiple of 32 bytes
+		assert_eq!(encode_packed(&vec!["ab", "c"]), hex!("
+			6162000000000000000000000000000000000000000000000000000000000000
+			6300000000000000000000000000000000000000000000000000000000000000
+		").to_vec());
+		assert_eq!(encode_packed(&[vec![0x12u8], vec![]]), hex!("
+			1200000000000000000000000000000000000000000000000000000000000000
+		").to_vec());
+		assert_eq!(encode_packed(&vec![-1i8]), vec![0xffu8; 32]);
+	}
+
+	#[test]
+	fn as_log() {
+		assert_eq!(true.as_log(), H256::from(hex!("0000000000000000000000000000000000000000000000000000000000000001")));
+		assert_eq!((-2i32).as_log(), H256::from(hex!("fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffe")));
+		assert_eq!((-2i64).as_log(), H256::from(hex!("fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffe")));
+		assert_eq!([0x12u8, 0x34].as_log(), H256::from(hex!("1234000000000000000000000000000000000000000000000000000000000000")));
+
+		// dynamic types are hashed
+		assert_eq!(String::from("abc").as_log(), H256::from(hex!("4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45")));
+		assert_eq!(b"abc".to_vec().as_log(), String::from("abc").as_log());
+		assert_eq!(vec![1u32, 2].as_log(), H256::from(hex!("e90b7bceb6e7df5418fb78d8ee546e97c83a08bbccc01a0644d599ccd2a7c2e0")));
+		assert_eq!(vec!["ab", "c"].as_log(), H256::from(hex!("ac410927311e8675d79aa8ee923c592524c93c4a436df8f4d3d5efe2b9d7b0a7")));
+		assert_eq!((&b"abc"[..]).as_log(), String::from("abc").as_log());
+		assert_eq!(AsLog::as_log(&"abc"), String::from("abc").as_log());
+
+		// fixed arrays and tuples are hashed as their padded elements
+		assert_eq!([1u32, 2].as_log(), vec![1u32, 2].as_log());
+		assert_eq!([[0x12u8, 0x34]].as_log(), keccak(&hex!("1234000000000000000000000000000000000000000000000000000000000000")));
+		assert_eq!((1u32, 2u32).as_log(), vec![1u32, 2].as_log());
+		assert_eq!((String::from("ab"), String::from("c")).as_log(), vec!["ab", "c"].as_log());
+	}
+
+	#[derive(Clone, Debug, PartialEq)]
+	struct Legacy(u32);
+
+	impl AbiType for Legacy {
+		fn decode(stream: &mut Stream) -> Result<Self, Error> {
+			Ok(Legacy(stream.pop()?))
+		}
+
+		fn encode(self, sink: &mut Sink) {
+			sink.push(self.0)
+		}
+
+		const IS_FIXED: bool = true;
+	}
+
+	#[test]
+	fn legacy_abi_type() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000045
+			0000000000000000000000000000000000000000000000000000000000000046
+		");
+		assert_conformance(vec![Legacy(69), Legacy(70)], &encoded);
+	}
+
+	#[test]
+	fn error_context() {
+		// second element of the second array is not a bool
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000002
+		");
+		let error = Stream::new(&encoded).pop::<Vec<[bool; 2]>>().unwrap_err();
+		assert_eq!(error.kind(), ErrorKind::InvalidBool);
+		assert_eq!(error.position(), Some(160));
+		assert_eq!(error.expected(), Some("bool"));
+		assert_eq!(error.path(), &[PathSegment::Index(1), PathSegment::Index(1)][..]);
+		assert_eq!(error.to_string(), "invalid bool decoding bool at byte 160 in [1][1]");
+
+		// last member of the tuple is not a bool
+		let error = Stream::new(&encoded[64..]).pop::<(bool, bool, bool, bool)>().unwrap_err();
+		assert_eq!(error.position(), Some(96));
+		assert_eq!(error.path(), &[PathSegment::Member(3)][..]);
+		assert_eq!(error.to_string(), "invalid bool decoding bool at byte 96 in .3");
+	}
+
+	#[test]
+	fn error_path_depth() {
+		let mut error = Error::from(ErrorKind::InvalidBool);
+		for index in 0..MAX_PATH_DEPTH + 2 {
+			error = error.at_index(index);
+		}
+		// The innermost segments beyond the maximum depth are dropped.
+		let path = (2..MAX_PATH_DEPTH + 2).rev().map(PathSegment::Index).collect::<Vec<_>>();
+		assert_eq!(error.path(), &path[..]);
+		assert_eq!(error.at_argument(0).path()[..2], [PathSegment::Argument(0), PathSegment::Index(MAX_PATH_DEPTH + 1)]);
+	}
+
+	#[test]
+	fn error_kind_compat() {
+		let error = Stream::new(&[0u8; 31]).pop::<bool>().unwrap_err();
+		assert_eq!(error, ErrorKind::UnexpectedEof);
+		assert_eq!(error.kind(), Error::UnexpectedEof.kind());
+		assert_eq!(Error::InvalidBool, Error::from(ErrorKind::InvalidBool));
+		assert!(Error::InvalidBool != ErrorKind::InvalidU32);
+	}
+
+	/// Encodes `value` as the only argument of a call and decodes it back,
+	/// both have to match the reference encoding produced by solc and ethabi.
+	fn assert_conformance<T: AbiEncode + for<'a> AbiDecode<'a> + Clone + PartialEq + Debug>(value: T, encoded: &[u8]) {
+		let mut sink = Sink::new();
+		sink.push(value.clone());
+		assert_eq!(sink.finalize().unwrap(), encoded.to_vec());
+
+		let mut stream = Stream::new(encoded);
+		assert_eq!(stream.pop::<T>().unwrap(), value);
+
+		let mut stream = Stream::new_strict(encoded);
+		assert_eq!(stream.pop::<T>().unwrap(), value);
+		assert_eq!(stream.finish(), Ok(()));
+	}
+
+	// g(uint256[][],string[]) example of the Solidity ABI specification
+	#[test]
+	fn conformance_spec_nested_arrays() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000140
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000040
+			00000000000000000000000000000000000000000000000000000000000000a0
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000003
+			0000000000000000000000000000000000000000000000000000000000000003
+			0000000000000000000000000000000000000000000000000000000000000060
+			00000000000000000000000000000000000000000000000000000000000000a0
+			00000000000000000000000000000000000000000000000000000000000000e0
+			0000000000000000000000000000000000000000000000000000000000000003
+			6f6e650000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000003
+			74776f0000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000005
+			7468726565000000000000000000000000000000000000000000000000000000
+		");
+		let numbers = vec![vec![U256::from(1), U256::from(2)], vec![U256::from(3)]];
+		let strings = vec![String::from("one"), String::from("two"), String::from("three")];
+
+		let mut sink = Sink::with_head_size(64);
+		sink.push(numbers.clone());
+		sink.push(strings.clone());
+		assert_eq!(sink.finalize().unwrap(), encoded.to_vec());
+
+		let mut stream = Stream::new(&encoded);
+		assert_eq!(stream.pop::<Vec<Vec<U256>>>().unwrap(), numbers);
+		assert_eq!(stream.pop::<Vec<String>>().unwrap(), strings);
+	}
+
+	// f(uint256,uint32[],bytes10,bytes) example of the Solidity ABI specification
+	#[test]
+	fn conformance_spec_mixed() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000123
+			0000000000000000000000000000000000000000000000000000000000000080
+			3132333435363738393000000000000000000000000000000000000000000000
+			00000000000000000000000000000000000000000000000000000000000000e0
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000456
+			0000000000000000000000000000000000000000000000000000000000000789
+			000000000000000000000000000000000000000000000000000000000000000d
+			48656c6c6f2c20776f726c642100000000000000000000000000000000000000
+		");
+
+		let mut sink = Sink::with_head_size(128);
+		sink.push(U256::from(0x123));
+		sink.push(vec![0x456u32, 0x789]);
+		sink.push(*b"1234567890");
+		sink.push(b"Hello, world!".to_vec());
+		assert_eq!(sink.finalize().unwrap(), encoded.to_vec());
+
+		let mut stream = Stream::new(&encoded);
+		assert_eq!(stream.pop::<U256>().unwrap(), U256::from(0x123));
+		assert_eq!(stream.pop::<Vec<u32>>().unwrap(), vec![0x456, 0x789]);
+		assert_eq!(&stream.pop::<[u8; 10]>().unwrap(), b"1234567890");
+		assert_eq!(stream.pop::<Vec<u8>>().unwrap(), b"Hello, world!".to_vec());
+	}
+
+	// bytes[]
+	#[test]
+	fn conformance_vec_of_bytes() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000003
+			0000000000000000000000000000000000000000000000000000000000000060
+			00000000000000000000000000000000000000000000000000000000000000a0
+			00000000000000000000000000000000000000000000000000000000000000c0
+			0000000000000000000000000000000000000000000000000000000000000002
+			1234000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000021
+			0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20
+			2100000000000000000000000000000000000000000000000000000000000000
+		");
+		let value: Vec<Vec<u8>> = vec![vec![0x12, 0x34], Vec::new(), (1u8..34).collect()];
+		assert_conformance(value, &encoded);
+	}
+
+	// string[]
+	#[test]
+	fn conformance_vec_of_strings() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000003
+			0000000000000000000000000000000000000000000000000000000000000060
+			00000000000000000000000000000000000000000000000000000000000000a0
+			00000000000000000000000000000000000000000000000000000000000000e0
+			0000000000000000000000000000000000000000000000000000000000000003
+			6f6e650000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000003
+			74776f0000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000005
+			7468726565000000000000000000000000000000000000000000000000000000
+		");
+		let value: Vec<String> = vec![String::from("one"), String::from("two"), String::from("three")];
+		assert_conformance(value, &encoded);
+	}
+
+	// string[]
+	#[test]
+	fn conformance_vec_of_strings_empty() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000000
+		");
+		let value: Vec<String> = Vec::new();
+		assert_conformance(value, &encoded);
+	}
+
+	// string[]
+	#[test]
+	fn conformance_vec_of_empty_strings() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000060
+			0000000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000000
+		");
+		let value: Vec<String> = vec![String::new(), String::new()];
+		assert_conformance(value, &encoded);
+	}
+
+	// uint256[][]
+	#[test]
+	fn conformance_vec_of_vec_u256() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000003
+			0000000000000000000000000000000000000000000000000000000000000060
+			00000000000000000000000000000000000000000000000000000000000000c0
+			0000000000000000000000000000000000000000000000000000000000000100
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000003
+			0000000000000000000000000000000000000000000000000000000000000000
+		");
+		let value: Vec<Vec<U256>> = vec![vec![U256::from(1), U256::from(2)], vec![U256::from(3)], Vec::new()];
+		assert_conformance(value, &encoded);
+	}
+
+	// string[][]
+	#[test]
+	fn conformance_vec_of_vec_of_strings() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000040
+			00000000000000000000000000000000000000000000000000000000000000c0
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000001
+			6100000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000080
+			0000000000000000000000000000000000000000000000000000000000000001
+			6200000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000001
+			6300000000000000000000000000000000000000000000000000000000000000
+		");
+		let value: Vec<Vec<String>> = vec![vec![String::from("a")], vec![String::from("b"), String::from("c")]];
+		assert_conformance(value, &encoded);
+	}
+
+	// uint32[][][]
+	#[test]
+	fn conformance_vec_of_vec_of_vec_u32() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000003
+			0000000000000000000000000000000000000000000000000000000000000060
+			0000000000000000000000000000000000000000000000000000000000000160
+			0000000000000000000000000000000000000000000000000000000000000180
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000080
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000003
+			0000000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000004
+		");
+		let value: Vec<Vec<Vec<u32>>> = vec![vec![vec![1u32], vec![2, 3]], Vec::new(), vec![vec![4]]];
+		assert_conformance(value, &encoded);
+	}
+
+	// uint256[]
+	#[test]
+	fn conformance_vec_of_u256() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000003
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000003
+		");
+		let value: Vec<U256> = vec![U256::from(1), U256::from(2), U256::from(3)];
+		assert_conformance(value, &encoded);
+	}
+
+	// address[]
+	#[test]
+	fn conformance_vec_of_addresses() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000101010101010101010101010101010101010101
+			0000000000000000000000000202020202020202020202020202020202020202
+		");
+		let value: Vec<Address> = vec![Address::from([1u8; 20]), Address::from([2u8; 20])];
+		assert_conformance(value, &encoded);
+	}
+
+	// bool[]
+	#[test]
+	fn conformance_vec_of_bools() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000003
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000001
+		");
+		let value: Vec<bool> = vec![true, false, true];
+		assert_conformance(value, &encoded);
+	}
+
+	// int8[]
+	#[test]
+	fn conformance_vec_of_i8() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000003
+			ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff
+			0000000000000000000000000000000000000000000000000000000000000001
+			ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff80
+		");
+		let value: Vec<i8> = vec![-1i8, 1, -128];
+		assert_conformance(value, &encoded);
+	}
+
+	// bytes4[]
+	#[test]
+	fn conformance_vec_of_bytes4() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000002
+			deadbeef00000000000000000000000000000000000000000000000000000000
+			0102030400000000000000000000000000000000000000000000000000000000
+		");
+		let value: Vec<[u8; 4]> = vec![[0xde, 0xad, 0xbe, 0xef], [0x01, 0x02, 0x03, 0x04]];
+		assert_conformance(value, &encoded);
+	}
+
+	// (uint32,bool)[]
+	#[test]
+	fn conformance_vec_of_static_tuples() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000000
+		");
+		let value: Vec<(u32, bool)> = vec![(1u32, true), (2u32, false)];
+		assert_conformance(value, &encoded);
+	}
+
+	// (uint256,string)[]
+	#[test]
+	fn conformance_vec_of_dynamic_tuples() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000040
+			00000000000000000000000000000000000000000000000000000000000000c0
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000001
+			7800000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000002
+			797a000000000000000000000000000000000000000000000000000000000000
+		");
+		let value: Vec<(U256, String)> = vec![(U256::from(1), String::from("x")), (U256::from(2), String::from("yz"))];
+		assert_conformance(value, &encoded);
+	}
+
+	// (bytes,string[])[]
+	#[test]
+	fn conformance_vec_of_nested_tuples() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000040
+			00000000000000000000000000000000000000000000000000000000000001a0
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000080
+			0000000000000000000000000000000000000000000000000000000000000001
+			ff00000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000080
+			0000000000000000000000000000000000000000000000000000000000000001
+			7000000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000001
+			7100000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000060
+			0000000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000000
+		");
+		let value: Vec<(Vec<u8>, Vec<String>)> = vec![(vec![0xffu8], vec![String::from("p"), String::from("q")]), (Vec::new(), Vec::new())];
+		assert_conformance(value, &encoded);
+	}
+
+	// uint256[][2]
+	#[test]
+	fn conformance_fixed_array_of_vec() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000080
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000003
+		");
+		let value: [Vec<U256>; 2] = [vec![U256::from(1)], vec![U256::from(2), U256::from(3)]];
+		assert_conformance(value, &encoded);
+	}
+
+	// uint256[2][]
+	#[test]
+	fn conformance_vec_of_fixed_arrays() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000003
+			0000000000000000000000000000000000000000000000000000000000000004
+		");
+		let value: Vec<[U256; 2]> = vec![[U256::from(1), U256::from(2)], [U256::from(3), U256::from(4)]];
+		assert_conformance(value, &encoded);
+	}
+
+	// string[2][]
+	#[test]
+	fn conformance_vec_of_fixed_string_arrays() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000002
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000100
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000080
+			0000000000000000000000000000000000000000000000000000000000000001
+			6100000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000001
+			6200000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000040
+			0000000000000000000000000000000000000000000000000000000000000080
+			0000000000000000000000000000000000000000000000000000000000000001
+			6300000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000001
+			6400000000000000000000000000000000000000000000000000000000000000
+		");
+		let value: Vec<[String; 2]> = vec![[String::from("a"), String::from("b")], [String::from("c"), String::from("d")]];
+		assert_conformance(value, &encoded);
+	}
+
+	// (string[],bool,bytes)
+	#[test]
+	fn conformance_tuple_of_vecs() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000060
+			0000000000000000000000000000000000000000000000000000000000000001
+			00000000000000000000000000000000000000000000000000000000000000e0
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000001
+			7800000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000001
+			ff00000000000000000000000000000000000000000000000000000000000000
+		");
+		let value: (Vec<String>, bool, Vec<u8>) = (vec![String::from("x")], true, vec![0xffu8]);
+		assert_conformance(value, &encoded);
+	}
+
+	// string
+	#[test]
+	fn conformance_string_of_word_size() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000020
+			6162636465666768696a6b6c6d6e6f707172737475767778797a303132333435
+		");
+		let value: String = String::from("abcdefghijklmnopqrstuvwxyz012345");
+		assert_conformance(value, &encoded);
+	}
+
+	// bytes
+	#[test]
+	fn conformance_bytes_over_word_size() {
+		let encoded = hex!("
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000021
+			0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20
+			2100000000000000000000000000000000000000000000000000000000000000
+		");
+		let value: Vec<u8> = (1u8..34).collect();
+		assert_conformance(value, &encoded);
+	}
+}
+
+#[cfg(feature = "std")]
+mod alloc_free {
+	use std::alloc::{GlobalAlloc, Layout, System};
+	use std::cell::Cell;
+	use super::super::*;
+	use super::super::types::*;
+
+	/// Allocator counting the allocations of every thread
+	struct CountingAlloc;
+
+	thread_local! {
+		static ALLOCATIONS: Cell<usize> = Cell::new(0);
+	}
+
+	unsafe impl GlobalAlloc for CountingAlloc {
+		unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+			let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+			System.alloc(layout)
+		}
+
+		unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+			System.dealloc(ptr, layout)
+		}
+	}
+
+	#[global_allocator]
+	static ALLOCATOR: CountingAlloc = CountingAlloc;
+
+	fn allocations() -> usize {
+		ALLOCATIONS.with(|count| count.get())
+	}
+
+	#[test]
+	fn fixed_buffer_encode_without_allocation() {
+		let (amount, owner) = (U256::from(69), Address::from([0x11u8; 20]));
+		let mut buffer = [0u8; 160];
+		let before = allocations();
+
+		let mut sink = Sink::from_slice(&mut buffer);
+		sink.reserve(128);
+		sink.push(amount);
+		sink.push(owner);
+		sink.push(true);
+		sink.push([7u8; 32]);
+		let written = sink.finish();
+
+		let mut short_buffer = [0u8; 32];
+		let mut sink = Sink::from_slice(&mut short_buffer);
+		sink.push((amount, true));
+		let overflow = sink.finish();
+
+		assert_eq!(allocations(), before);
+		assert_eq!(written, Ok(128));
+		assert_eq!(&buffer[..32], &util::pad_u32(69)[..]);
+		assert_eq!(&buffer[96..128], &[7u8; 32][..]);
+		assert_eq!(overflow, Err(SinkError::Overflow { required: 64, capacity: 32 }));
+	}
 }
 
 #[cfg(feature = "std")]
@@ -75,12 +1156,12 @@ fn simple() {
 	assert_eq!(val, 69);
 }
 
-fn single_decode<T: super::AbiType>(payload: &[u8]) -> (T) {
+fn single_decode<'a, T: super::AbiDecode<'a>>(payload: &'a [u8]) -> (T) {
 	let mut stream = super::Stream::new(payload);
 	stream.pop().expect("argument type 1 should be decoded")
 }
 
-fn double_decode<T1: super::AbiType, T2: super::AbiType>(payload: &[u8]) -> (T1, T2) {
+fn double_decode<'a, T1: super::AbiDecode<'a>, T2: super::AbiDecode<'a>>(payload: &'a [u8]) -> (T1, T2) {
 	let mut stream = super::Stream::new(payload);
 	(
 		stream.pop().expect("argument type 1 should be decoded"),
@@ -88,7 +1169,7 @@ fn double_decode<T1: super::AbiType, T2: super::AbiType>(payload: &[u8]) -> (T1,
 	)
 }
 
-fn triple_decode<T1: super::AbiType, T2: super::AbiType, T3: super::AbiType>(payload: &[u8]) -> (T1, T2, T3) {
+fn triple_decode<'a, T1: super::AbiDecode<'a>, T2: super::AbiDecode<'a>, T3: super::AbiDecode<'a>>(payload: &'a [u8]) -> (T1, T2, T3) {
 	let mut stream = super::Stream::new(payload);
 	(
 		stream.pop().expect("argument type 1 should be decoded"),
@@ -97,10 +1178,10 @@ fn triple_decode<T1: super::AbiType, T2: super::AbiType, T3: super::AbiType>(pay
 	)
 }
 
-fn single_encode<T: super::AbiType>(val: T) -> Vec<u8> {
-	let mut sink = super::Sink::new(1);
+fn single_encode<T: super::AbiEncode>(val: T) -> Vec<u8> {
+	let mut sink = super::Sink::new();
 	sink.push(val);
-	sink.finalize_panicking()
+	sink.finalize().unwrap()
 }
 
 #[test]
@@ -140,6 +1221,61 @@ fn fixed_size_decode() {
 	]);
 }
 
+#[test]
+#[should_panic]
+fn unreserved_heads() {
+	// the head of `true` would be written over the bytes
+	let mut sink = Sink::new();
+	sink.push(vec![1u8, 2, 3]);
+	sink.push(true);
+	sink.finalize().unwrap();
+}
+
+#[test]
+fn finalize_errors() {
+	let mut sink = Sink::new();
+	sink.push(vec![1u8, 2, 3]);
+	sink.push(true);
+	assert_eq!(sink.finalize(), Err(SinkError::InconsistentOffset { position: 32 }));
+
+	let mut sink = Sink::with_head_size(64);
+	sink.push(1u32);
+	assert_eq!(sink.finalize(), Err(SinkError::Underflow { pushed: 32, reserved: 64 }));
+
+	let mut sink = Sink::with_head_size(32);
+	sink.push((1u32, 2u32));
+	assert_eq!(sink.finalize(), Err(SinkError::HeadOverflow { reserved: 32 }));
+
+	let mut sink = Sink::with_head_size(64);
+	sink.push(vec![1u8, 2, 3]);
+	sink.push(true);
+	// sequence of values is encoded like a tuple without its offset
+	assert_eq!(sink.finalize(), Ok(single_encode((vec![1u8, 2, 3], true))[32..].to_vec()));
+}
+
+#[test]
+fn fixed_buffer_encode() {
+	let mut buffer = [0xffu8; 96];
+	let mut sink = Sink::from_slice(&mut buffer);
+	sink.push((U256::from(69), true));
+	assert_eq!(sink.finish(), Ok(64));
+	assert_eq!(&buffer[..64], &single_encode((U256::from(69), true))[..]);
+	assert_eq!(&buffer[64..], &[0xffu8; 32][..]);
+
+	// dynamic values are written to the end of the buffer as well
+	let mut sink = Sink::from_slice(&mut buffer);
+	sink.push(vec![1u8, 2, 3]);
+	assert_eq!(sink.finish(), Ok(96));
+	assert_eq!(&buffer[..], &single_encode(vec![1u8, 2, 3])[..]);
+
+	let mut buffer = [0u8; 32];
+	let mut sink = Sink::from_slice(&mut buffer);
+	sink.reserve(64);
+	sink.push(U256::from(69));
+	sink.push(true);
+	assert_eq!(sink.finish(), Err(SinkError::Overflow { required: 64, capacity: 32 }));
+}
+
 #[test]
 fn sample1_encode() {
 	let sample: &[u8] = &[
@@ -147,11 +1283,11 @@ fn sample1_encode() {
 		0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,
 	];
 
-	let mut sink = Sink::new(2);
+	let mut sink = Sink::new();
 	sink.push(69u32);
 	sink.push(true);
 
-	assert_eq!(&sink.finalize_panicking()[..], &sample[..]);
+	assert_eq!(&sink.finalize().unwrap()[..], &sample[..]);
 }
 
 #[test]
@@ -178,9 +1314,9 @@ fn sample2_decode() {
 #[test]
 fn negative_i32() {
 	let x: i32 = -1;
-	let mut sink = ::eth::Sink::new(1);
+	let mut sink = ::eth::Sink::new();
 	sink.push(x);
-	let payload = sink.finalize_panicking();
+	let payload = sink.finalize().unwrap();
 
 	assert_eq!(
 		&payload[..],
@@ -195,9 +1331,9 @@ fn negative_i32() {
 #[test]
 fn negative_i32_max() {
 	let x: i32 = i32::min_value();
-	let mut sink = ::eth::Sink::new(1);
+	let mut sink = ::eth::Sink::new();
 	sink.push(x);
-	let payload = sink.finalize_panicking();
+	let payload = sink.finalize().unwrap();
 
 	assert_eq!(
 		&payload[..],
@@ -217,7 +1353,7 @@ fn padding_test_i32() {
 	let mut sample = [0xff; 32];
 	sample[0] = 0x80;
 	let mut stream = ::eth::Stream::new(&sample);
-	assert_eq!(stream.pop::<i32>().unwrap_err(), Error::InvalidPadding);
+	assert_eq!(stream.pop::<i32>().unwrap_err().kind(), ErrorKind::InvalidPadding);
 }
 
 #[test]
@@ -225,7 +1361,51 @@ fn padding_test_i64() {
 	let mut sample = [0xff; 32];
 	sample[0] = 0x80;
 	let mut stream = ::eth::Stream::new(&sample);
-	assert_eq!(stream.pop::<i64>().unwrap_err(), Error::InvalidPadding);
+	assert_eq!(stream.pop::<i64>().unwrap_err().kind(), ErrorKind::InvalidPadding);
+}
+
+#[test]
+fn small_uint_encode_decode() {
+	let mut expected = [0u8; 32];
+	expected[31] = 0xfe;
+	assert_eq!(&single_encode(0xfeu8)[..], &expected[..]);
+	assert_eq!(single_decode::<u8>(&expected), 0xfe);
+
+	expected[30] = 0x01;
+	assert_eq!(single_decode::<u16>(&expected), 0x01fe);
+	assert_eq!(Stream::new(&expected).pop::<u8>().unwrap_err().kind(), ErrorKind::InvalidU8);
+
+	let max = single_encode(u128::max_value());
+	assert_eq_core!(&max[..16], &[0u8; 16]);
+	assert_eq_core!(&max[16..], &[0xffu8; 16]);
+	assert_eq!(single_decode::<u128>(&max), u128::max_value());
+	assert_eq!(Stream::new(&[0xff; 32]).pop::<u128>().unwrap_err().kind(), ErrorKind::InvalidU128);
+}
+
+#[test]
+fn small_int_encode_decode() {
+	assert_eq_core!(&single_encode(-1i8)[..], &[0xffu8; 32][..]);
+	assert_eq!(single_decode::<i8>(&[0xff; 32]), -1);
+	assert_eq!(single_decode::<i16>(&single_encode(i16::min_value())), i16::min_value());
+	assert_eq!(single_decode::<i128>(&single_encode(i128::min_value())), i128::min_value());
+	assert_eq!(single_decode::<i128>(&single_encode(i128::max_value())), i128::max_value());
+
+	// 128 is out of range of int8 even though it fits into the lowest byte
+	let mut sample = [0u8; 32];
+	sample[31] = 0x80;
+	assert_eq!(Stream::new(&sample).pop::<i8>().unwrap_err().kind(), ErrorKind::InvalidPadding);
+}
+
+#[test]
+fn int256_encode_decode() {
+	assert_eq_core!(&single_encode(I256::from(-1i8))[..], &[0xffu8; 32][..]);
+	assert_eq!(single_decode::<I256>(&[0xff; 32]), I256::from(-1i8));
+
+	let min = single_encode(I256::from(i128::min_value()));
+	assert_eq!(single_decode::<I256>(&min), I256::from(i128::min_value()));
+	assert!(I256::from(-2i8) < I256::from(-1i8));
+	assert!(I256::from(-1i8) < I256::zero());
+	assert!(I256::zero() < I256::from(1i8));
 }
 
 
@@ -233,9 +1413,9 @@ fn padding_test_i64() {
 fn string_encode_decode() {
 	let test_string = String::from("Parity Röcks!");
 
-	let mut sink = Sink::new(1);
+	let mut sink = Sink::new();
 	sink.push(test_string.clone());
-	let payload = sink.finalize_panicking();
+	let payload = sink.finalize().unwrap();
 
 	// The binary data is the "preamble", followed by the nested variable size data, starting with the
 	// length of the string (in bytes) as uint256 followed by the UTF-8 encoded string, padded to 32 bytes.
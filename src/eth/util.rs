@@ -1,6 +1,8 @@
 //! Utility module
 
 use lib::*;
+use tiny_keccak::Keccak;
+use super::types::H256;
 
 pub type Hash = [u8; 32];
 
@@ -58,4 +60,30 @@ pub fn pad_i32(value: i32) -> Hash {
 	padded[30] = (value >> 8) as u8;
 	padded[31] = value as u8;
 	padded
-}
\ No newline at end of file
+}
+/// Converts u128 to right aligned array of 32 bytes.
+pub fn pad_u128(value: u128) -> Hash {
+	let mut padded = [0u8; 32];
+	for i in 0..16 {
+		padded[31 - i] = (value >> (8 * i)) as u8;
+	}
+	padded
+}
+
+/// Converts i128 to right aligned, sign extended array of 32 bytes.
+pub fn pad_i128(value: i128) -> Hash {
+	let mut padded = if value < 0 { [0xffu8; 32] } else { [0u8; 32] };
+	for i in 0..16 {
+		padded[31 - i] = (value >> (8 * i)) as u8;
+	}
+	padded
+}
+
+/// Keccak-256 hash of `bytes`, as used for event topics and selectors.
+pub fn keccak(bytes: &[u8]) -> H256 {
+	let mut keccak = Keccak::new_keccak256();
+	let mut result = H256::zero();
+	keccak.update(bytes);
+	keccak.finalize(result.as_mut());
+	result
+}
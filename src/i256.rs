@@ -0,0 +1,72 @@
+//! Signed 256-bit integer
+
+use lib::*;
+use pwasm_std::types::U256;
+
+/// Signed 256-bit integer (Solidity `int256`) in two's complement representation
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct I256(U256);
+
+impl I256 {
+	/// Zero value
+	pub fn zero() -> Self {
+		I256(U256::zero())
+	}
+
+	/// Value from its two's complement representation
+	pub fn from_twos_complement(value: U256) -> Self {
+		I256(value)
+	}
+
+	/// Two's complement representation of the value
+	pub fn into_twos_complement(self) -> U256 {
+		self.0
+	}
+
+	/// Whether the value is below zero
+	pub fn is_negative(&self) -> bool {
+		self.0.bit(255)
+	}
+}
+
+impl From<i128> for I256 {
+	fn from(value: i128) -> Self {
+		let mut bytes = if value < 0 { [0xffu8; 32] } else { [0u8; 32] };
+		for i in 0..16 {
+			bytes[31 - i] = (value >> (8 * i)) as u8;
+		}
+		I256(U256::from_big_endian(&bytes))
+	}
+}
+
+macro_rules! i256_from_impl {
+	($t: ty) => {
+		impl From<$t> for I256 {
+			fn from(value: $t) -> Self {
+				I256::from(value as i128)
+			}
+		}
+	}
+}
+
+i256_from_impl!(i8);
+i256_from_impl!(i16);
+i256_from_impl!(i32);
+i256_from_impl!(i64);
+
+impl PartialOrd for I256 {
+	fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for I256 {
+	fn cmp(&self, other: &Self) -> cmp::Ordering {
+		// Values of the same sign compare like their two's complement representations
+		match (self.is_negative(), other.is_negative()) {
+			(true, false) => cmp::Ordering::Less,
+			(false, true) => cmp::Ordering::Greater,
+			_ => self.0.cmp(&other.0),
+		}
+	}
+}
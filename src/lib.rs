@@ -1,28 +1,53 @@
 //! WASM ABI Tools
 
 #![cfg_attr(not(feature="std"), no_std)]
-#![cfg_attr(not(feature="std"), feature(alloc))]
+#![cfg_attr(all(not(feature="std"), feature="alloc"), feature(alloc))]
 #![warn(missing_docs)]
 #![cfg_attr(feature="strict", deny(unused))]
 
 extern crate byteorder;
+extern crate tiny_keccak;
 extern crate pwasm_std;
 
 #[cfg(test)]
 #[cfg_attr(all(test, feature = "std"), macro_use)]
 extern crate hex_literal;
 
-#[cfg(not(feature="std"))]
+#[cfg(all(not(feature="std"), feature="alloc"))]
 #[allow(unused)]
 #[macro_use] extern crate alloc;
 
 pub mod eth;
+mod i256;
 
 /// Custom types which AbiType supports
 pub mod types {
 	pub use pwasm_std::Vec;
 	pub use pwasm_std::String;
 	pub use pwasm_std::types::*;
+	pub use super::i256::I256;
+}
+
+/// Expands to the given items only with the `alloc` feature
+///
+/// Used by the derive macros for the items of their output that allocate,
+/// which depends on the features of this crate rather than of the caller.
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __with_alloc {
+	($($tokens:tt)*) => { $($tokens)* };
+}
+
+/// Expands to the given items only with the `alloc` feature
+///
+/// Used by the derive macros for the items of their output that allocate,
+/// which depends on the features of this crate rather than of the caller.
+#[cfg(not(feature = "alloc"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __with_alloc {
+	($($tokens:tt)*) => {};
 }
 
 mod lib {
@@ -49,21 +74,21 @@ mod lib {
 
 	#[cfg(feature = "std")]
 	pub use std::borrow::{Cow, ToOwned};
-	#[cfg(not(feature = "std"))]
+	#[cfg(all(not(feature = "std"), feature = "alloc"))]
 	pub use alloc::borrow::{Cow, ToOwned};
 
 	#[cfg(feature = "std")]
 	pub use std::string::String;
-	#[cfg(not(feature = "std"))]
+	#[cfg(all(not(feature = "std"), feature = "alloc"))]
 	pub use alloc::string::{String, ToString};
 
 	#[cfg(feature = "std")]
 	pub use std::vec::Vec;
-	#[cfg(not(feature = "std"))]
+	#[cfg(all(not(feature = "std"), feature = "alloc"))]
 	pub use alloc::vec::Vec;
 
 	#[cfg(feature = "std")]
 	pub use std::boxed::Box;
-	#[cfg(not(feature = "std"))]
+	#[cfg(all(not(feature = "std"), feature = "alloc"))]
 	pub use alloc::boxed::Box;
 }
@@ -0,0 +1,89 @@
+#![allow(dead_code)]
+
+use pwasm_test::{ext_get, ext_reset, Endpoint};
+use pwasm_abi::eth::EndpointInterface;
+use pwasm_abi_derive::eth_abi;
+use pwasm_abi::types::{H160, H256, U256};
+type Address = H160;
+
+#[eth_abi(NftEndpoint, NftClient)]
+pub trait NftContract {
+	#[constant]
+	#[abi(name = "balanceOf", selector = 0x70a08231)]
+	fn balance_of(&mut self, owner: Address) -> U256;
+
+	#[abi(name = "safeTransferFrom")]
+	fn safe_transfer_from(&mut self, from: Address, to: Address, token_id: U256);
+
+	#[abi(name = "safeTransferFrom")]
+	fn safe_transfer_from_with_data(&mut self, from: Address, to: Address, token_id: U256, data: Vec<u8>);
+
+	#[event]
+	#[abi(name = "Transfer")]
+	fn transferred(&mut self, #[indexed] from: Address, #[indexed] to: Address, #[indexed] token_id: U256);
+}
+
+#[derive(Default)]
+pub struct Instance {
+	transfers: Vec<(U256, Option<Vec<u8>>)>,
+}
+
+impl NftContract for Instance {
+	fn balance_of(&mut self, _owner: Address) -> U256 {
+		U256::from(7)
+	}
+
+	fn safe_transfer_from(&mut self, from: Address, to: Address, token_id: U256) {
+		self.transfers.push((token_id, None));
+		self.transferred(from, to, token_id).expect("transfer event encodes");
+	}
+
+	fn safe_transfer_from_with_data(&mut self, _from: Address, _to: Address, token_id: U256, data: Vec<u8>) {
+		self.transfers.push((token_id, Some(data)));
+	}
+}
+
+#[test]
+fn overloaded_selectors() {
+	ext_reset(|e| e.endpoint(Address::zero(), Endpoint::ok()));
+	let mut client = NftClient::new(Address::zero());
+	client.safe_transfer_from(Address::zero(), Address::zero(), U256::from(1));
+	client.safe_transfer_from_with_data(Address::zero(), Address::zero(), U256::from(2), vec![0xff]);
+	client.balance_of(Address::zero());
+
+	let calls = ext_get().calls();
+	// safeTransferFrom(address,address,uint256)
+	assert_eq!(&calls[0].input[..4], &[0x42, 0x84, 0x2e, 0x0e]);
+	// safeTransferFrom(address,address,uint256,bytes)
+	assert_eq!(&calls[1].input[..4], &[0xb8, 0x8d, 0x4f, 0xde]);
+	// balanceOf(address)
+	assert_eq!(&calls[2].input[..4], &[0x70, 0xa0, 0x82, 0x31]);
+
+	let mut endpoint = NftEndpoint::new(Instance::default());
+	for call in calls.iter() {
+		endpoint.dispatch(&call.input);
+	}
+	assert_eq!(
+		endpoint.inner.transfers,
+		vec![(U256::from(1), None), (U256::from(2), Some(vec![0xff]))]
+	);
+}
+
+#[test]
+fn renamed_constant_and_event() {
+	let mut endpoint = NftEndpoint::new(Instance::default());
+
+	// balanceOf(0x0)
+	let mut payload = vec![0x70, 0xa0, 0x82, 0x31];
+	payload.extend_from_slice(&[0u8; 32]);
+	let result = endpoint.dispatch(&payload);
+	assert_eq!(result[31], 7);
+
+	ext_reset(|e| e);
+	endpoint.inner.safe_transfer_from(Address::zero(), Address::zero(), U256::from(3));
+	let logs = ext_get().logs();
+	let (ref topics, _) = logs[0];
+	// Transfer(address,address,uint256)
+	assert_eq!(&topics[0].as_ref()[..4], &[0xdd, 0xf2, 0x52, 0xad]);
+	assert_eq!(topics[0], H256::from(nft_contract_events::Transferred::SIGNATURE));
+}
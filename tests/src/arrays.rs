@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 
 use pwasm_abi::eth::EndpointInterface;
+use pwasm_abi::types::{Address, U256};
 use pwasm_abi_derive::eth_abi;
 
 
@@ -37,3 +38,37 @@ fn bytes16() {
 	assert_eq!(endpoint.inner.v1, [0x12, 0x24, 0x36, 0x48, 0x60, 0x72, 0x84, 0x96]);
 	assert_eq!(endpoint.inner.v2, [0x07, 0x14, 0x21, 0x28, 0x35, 0x42, 0x49, 0x56]);
 }
+
+#[eth_abi(FixedArrayEndpoint)]
+pub trait FixedArrayContract {
+	fn signers(&mut self, signers: [Address; 3], point: [U256; 2]) -> [U256; 2];
+}
+
+#[test]
+fn fixed_arrays() {
+	pub struct Instance;
+
+	impl FixedArrayContract for Instance {
+		fn signers(&mut self, signers: [Address; 3], point: [U256; 2]) -> [U256; 2] {
+			assert_eq!(signers, [Address::from([1; 20]), Address::from([2; 20]), Address::from([3; 20])]);
+			[point[1], point[0]]
+		}
+	}
+
+	// signers(address[3],uint256[2])
+	let mut payload = vec![0x02, 0xd7, 0xe6, 0x6f];
+	for signer in 1..4 {
+		payload.extend_from_slice(&[0u8; 12]);
+		payload.extend_from_slice(&[signer; 20]);
+	}
+	for coordinate in 5..7 {
+		payload.extend_from_slice(&[0u8; 31]);
+		payload.push(coordinate);
+	}
+
+	let mut endpoint = FixedArrayEndpoint::new(Instance);
+	let result = endpoint.dispatch(&payload);
+
+	assert_eq!(&result[..32], &payload[4 + 4 * 32..4 + 5 * 32]);
+	assert_eq!(&result[32..], &payload[4 + 3 * 32..4 + 4 * 32]);
+}
@@ -0,0 +1,43 @@
+#![allow(dead_code)]
+
+use pwasm_abi::eth::{ArrayIter, EndpointInterface, Sink, Stream};
+use pwasm_abi::types::{Address, U256};
+use pwasm_abi_derive::eth_abi;
+use pwasm_test::{ext_get, ext_reset, Endpoint};
+
+#[eth_abi(BorrowedEndpoint, BorrowedClient)]
+pub trait BorrowedContract {
+	fn verify(&mut self, proof: &[u8], note: &str, leaves: ArrayIter<U256>) -> U256;
+}
+
+pub struct Instance;
+
+impl BorrowedContract for Instance {
+	fn verify(&mut self, proof: &[u8], note: &str, leaves: ArrayIter<U256>) -> U256 {
+		let mut result = U256::from(proof.len() + note.len());
+		for leaf in leaves {
+			result = result + leaf.expect("leaves are valid");
+		}
+		result
+	}
+}
+
+#[test]
+fn borrowed_call() {
+	ext_reset(|e| e.endpoint(Address::zero(), Endpoint::new(Box::new(|_, input, result| {
+		let output = BorrowedEndpoint::new(Instance).dispatch(input);
+		result[..output.len()].copy_from_slice(&output);
+		Ok(())
+	}))));
+
+	let mut sink = Sink::new();
+	sink.push(vec![U256::from(3), U256::from(4)]);
+	let encoded_leaves = sink.finalize().unwrap();
+	let leaves: ArrayIter<U256> = Stream::new(&encoded_leaves).pop().unwrap();
+
+	let mut client = BorrowedClient::new(Address::zero());
+	assert_eq!(client.verify(&[0x12, 0x34], "abc", leaves), U256::from(12));
+
+	// verify(bytes,string,uint256[])
+	assert_eq!(&ext_get().calls()[0].input[..4], &[0xd4, 0x72, 0xc1, 0x30]);
+}
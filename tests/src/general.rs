@@ -1,8 +1,8 @@
 
 use pwasm_test::{ext_get, ext_reset, Endpoint};
-use pwasm_abi::eth::EndpointInterface;
+use pwasm_abi::eth::{DispatchError, EndpointInterface, ErrorKind};
 use pwasm_abi_derive::eth_abi;
-use pwasm_abi::types::{H160, U256};
+use pwasm_abi::types::{H160, H256, I256, U256};
 type Address = H160;
 
 #[eth_abi(TestEndpoint, Client)]
@@ -15,6 +15,12 @@ pub trait TestContract {
 
 	#[event]
 	fn baz_fired(&mut self, indexed_p1: u32, p2: u32);
+
+	#[event]
+	fn transferred(&mut self, #[indexed] from: Address, #[indexed] to: Address, value: U256);
+
+	#[event(anonymous)]
+	fn anonymous_fired(&mut self, #[indexed] p1: u32, #[indexed] p2: u32, #[indexed] p3: u32, #[indexed] p4: bool);
 }
 
 const PAYLOAD_SAMPLE_1: &[u8] = &[
@@ -187,3 +193,160 @@ fn baz_call() {
 	client.baz(69, true);
 	assert_eq!(ext_get().calls()[0].input.as_ref(), PAYLOAD_SAMPLE_1);
 }
+
+#[test]
+fn baz_fired_decode() {
+	let mut topic = [0u8; 32];
+	topic[31] = 0x45;
+	let topics = [H256::from(test_contract_events::BazFired::SIGNATURE), H256::from(topic)];
+	let data = &PAYLOAD_SAMPLE_3[4..];
+
+	let event = test_contract_events::BazFired::decode_log(&topics, data).expect("baz_fired should be decoded");
+
+	assert_eq!(event.indexed_p1, 69);
+	assert_eq!(event.p2, 69);
+
+	assert_eq!(
+		test_contract_events::BazFired::decode_log(&topics[..1], data).map_err(|error| error.kind()).err(),
+		Some(ErrorKind::InvalidTopics)
+	);
+	assert_eq!(
+		test_contract_events::BazFired::decode_log(&[topics[1], topics[1]], data).map_err(|error| error.kind()).err(),
+		Some(ErrorKind::InvalidEventSignature)
+	);
+}
+
+#[test]
+fn event_attributes() {
+	struct TestContractInstance;
+
+	impl TestContract for TestContractInstance {
+		fn constructor(&mut self, _p1: bool) {}
+		fn baz(&mut self, _p1: u32, _p2: bool) {}
+		fn boo(&mut self, _arg: u32) -> u32 { 0 }
+		fn sam(&mut self, _p1: Vec<u8>, _p2: bool, _p3: Vec<U256>) {}
+	}
+
+	ext_reset(|e| e);
+	let mut instance = TestContractInstance;
+	let from = Address::from([0x11; 20]);
+	let to = Address::from([0x22; 20]);
+	assert_eq!(instance.transferred(from, to, U256::from(1000)), Ok(()));
+	assert_eq!(instance.anonymous_fired(1, 2, 3, true), Ok(()));
+
+	let logs = ext_get().logs();
+	assert_eq!(logs.len(), 2);
+
+	// Parameters with the `#[indexed]` attribute follow the signature topic.
+	let (ref topics, ref data) = logs[0];
+	assert_eq!(topics.len(), 3);
+	assert_eq!(topics[0], H256::from(test_contract_events::Transferred::SIGNATURE));
+	let event = test_contract_events::Transferred::decode_log(topics, data).expect("transferred should be decoded");
+	assert_eq!(event.from, from);
+	assert_eq!(event.to, to);
+	assert_eq!(event.value, U256::from(1000));
+
+	// Anonymous events have no signature topic.
+	let (ref topics, ref data) = logs[1];
+	assert_eq!(topics.len(), 4);
+	assert!(data.is_empty());
+	let event = test_contract_events::AnonymousFired::decode_log(topics, data).expect("anonymous_fired should be decoded");
+	assert_eq!((event.p1, event.p2, event.p3, event.p4), (1, 2, 3, true));
+	assert_eq!(
+		test_contract_events::AnonymousFired::decode_log(&topics[..3], data).map_err(|error| error.kind()).err(),
+		Some(ErrorKind::InvalidTopics)
+	);
+}
+
+#[test]
+fn try_dispatch_errors() {
+	#[derive(Default)]
+	struct TestContractInstance;
+
+	impl TestContract for TestContractInstance {
+		fn constructor(&mut self, _p1: bool) {}
+		fn baz(&mut self, _p1: u32, _p2: bool) {}
+		fn boo(&mut self, _arg: u32) -> u32 { 0 }
+		fn sam(&mut self, _p1: Vec<u8>, _p2: bool, _p3: Vec<U256>) {}
+	}
+
+	let mut endpoint = TestEndpoint::new(TestContractInstance::default());
+
+	assert_eq!(endpoint.try_dispatch(&PAYLOAD_SAMPLE_1[..3]), Err(DispatchError::ShortPayload));
+	assert_eq!(endpoint.try_dispatch(&[0xff, 0xff, 0xff, 0xff]), Err(DispatchError::InvalidSelector));
+	match endpoint.try_dispatch(&PAYLOAD_SAMPLE_1[..40]) {
+		Err(DispatchError::ArgumentDecode { index: 1, error }) => {
+			assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+			assert_eq!(error.position(), Some(32));
+			assert_eq!(error.expected(), Some("bool"));
+			assert_eq!(error.to_string(), "unexpected end of payload decoding bool at byte 32 in args[1]");
+		},
+		other => panic!("Unexpected dispatch result {:?}", other),
+	}
+	match endpoint.try_dispatch_ctor(&[0x02; 32]) {
+		Err(DispatchError::ArgumentDecode { index: 0, error }) => assert_eq!(error.kind(), ErrorKind::InvalidU32),
+		other => panic!("Unexpected dispatch result {:?}", other),
+	}
+	assert_eq!(endpoint.try_dispatch(PAYLOAD_SAMPLE_1), Ok(Vec::new()));
+}
+
+#[eth_abi(HashEndpoint)]
+pub trait HashContract {
+	fn store(&mut self, key: H256, owner: H160) -> H256;
+}
+
+#[test]
+fn hash_types_canonical() {
+	struct HashContractInstance;
+
+	impl HashContract for HashContractInstance {
+		fn store(&mut self, key: H256, owner: H160) -> H256 {
+			assert_eq!(owner, H160::from([0x22; 20]));
+			key
+		}
+	}
+
+	// store(bytes32,address)
+	let mut payload = vec![0x05, 0xa1, 0x0a, 0x6d];
+	payload.extend_from_slice(&[0x11; 32]);
+	payload.extend_from_slice(&[0x00; 12]);
+	payload.extend_from_slice(&[0x22; 20]);
+
+	let mut endpoint = HashEndpoint::new(HashContractInstance);
+	assert_eq!(endpoint.dispatch(&payload), vec![0x11; 32]);
+}
+
+#[eth_abi(IntEndpoint)]
+pub trait IntContract {
+	fn scale(&mut self, decimals: u8, delta: I256) -> i128;
+}
+
+#[test]
+fn int_widths_canonical() {
+	struct IntContractInstance;
+
+	impl IntContract for IntContractInstance {
+		fn scale(&mut self, decimals: u8, delta: I256) -> i128 {
+			assert_eq!(delta, I256::from(-1i8));
+			-(decimals as i128)
+		}
+	}
+
+	// scale(uint8,int256)
+	let mut payload = vec![0x08, 0xb7, 0x75, 0x52];
+	payload.extend_from_slice(&[0x00; 31]);
+	payload.push(18);
+	payload.extend_from_slice(&[0xff; 32]);
+
+	let mut expected = vec![0xff; 31];
+	expected.push(0xee);
+
+	let mut endpoint = IntEndpoint::new(IntContractInstance);
+	assert_eq!(endpoint.dispatch(&payload), expected);
+
+	payload[4] = 0x01;
+	match endpoint.try_dispatch(&payload) {
+		Err(DispatchError::ArgumentDecode { index: 0, error }) => assert_eq!(error.kind(), ErrorKind::InvalidU8),
+		other => panic!("Unexpected dispatch result {:?}", other),
+	}
+}
@@ -17,3 +17,8 @@ mod trivia;
 mod payable;
 mod multiple_return;
 mod general;
+mod structs;
+mod revert;
+mod borrowed;
+mod abi_names;
+mod strict;
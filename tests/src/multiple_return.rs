@@ -1,31 +1,49 @@
 #![allow(dead_code)]
 
-use pwasm_abi::eth::EndpointInterface;
+use pwasm_abi::eth::{CallError, EndpointInterface, ErrorKind};
+use pwasm_abi::types::Address;
 use pwasm_abi_derive::eth_abi;
+use pwasm_test::{ext_reset, Endpoint};
 
 #[eth_abi(TupleReturnEndpoint, TupleReturnClient)]
 pub trait TupleReturnContract {
 	fn ret2(&mut self) -> (u64, u64);
 	fn ret6(&mut self) -> (u64, u64, u64, u64, u64, u64);
 	fn ret_var(&mut self) -> (u64, Vec<u8>);
+	fn tuple_arg(&mut self, v: (u64, Vec<u8>)) -> u64;
+	fn ret_large(&mut self, len: u64) -> Vec<u8>;
+	fn ret_zeros(&mut self, len: u64) -> Vec<u64>;
+	fn ret_flag(&mut self) -> bool;
 }
 
-#[test]
-fn multiple_return() {
-	pub struct Instance;
+pub struct Instance;
 
-	impl TupleReturnContract for Instance {
-		fn ret2(&mut self) -> (u64, u64) {
-			(2, 2)
-		}
-		fn ret6(&mut self) -> (u64, u64, u64, u64, u64, u64) {
-			(6, 6, 6, 6, 6, 6)
-		}
-		fn ret_var(&mut self) -> (u64, Vec<u8>) {
-			(6, vec![1, 2, 3, 5, 7, 11])
-		}
+impl TupleReturnContract for Instance {
+	fn ret2(&mut self) -> (u64, u64) {
+		(2, 2)
+	}
+	fn ret6(&mut self) -> (u64, u64, u64, u64, u64, u64) {
+		(6, 6, 6, 6, 6, 6)
+	}
+	fn ret_var(&mut self) -> (u64, Vec<u8>) {
+		(6, vec![1, 2, 3, 5, 7, 11])
+	}
+	fn tuple_arg(&mut self, v: (u64, Vec<u8>)) -> u64 {
+		v.0 + v.1.len() as u64
 	}
+	fn ret_large(&mut self, len: u64) -> Vec<u8> {
+		vec![7; len as usize]
+	}
+	fn ret_zeros(&mut self, len: u64) -> Vec<u64> {
+		vec![0; len as usize]
+	}
+	fn ret_flag(&mut self) -> bool {
+		true
+	}
+}
 
+#[test]
+fn multiple_return() {
 	let mut endpoint = TupleReturnEndpoint::new(Instance);
 
 	let res2 = endpoint.dispatch(&[0xa6, 0x37, 0xe6, 0x9c]);
@@ -51,4 +69,79 @@ fn multiple_return() {
 		0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 6,
 		1, 2, 3, 5, 7, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0
 	][..]);
-}
\ No newline at end of file
+}
+
+#[test]
+fn multiple_return_call() {
+	ext_reset(|e| e.endpoint(Address::zero(), Endpoint::new(Box::new(|_, input, result| {
+		let output = TupleReturnEndpoint::new(Instance).dispatch(input);
+		result[..output.len()].copy_from_slice(&output);
+		Ok(())
+	}))));
+
+	let mut client = TupleReturnClient::new(Address::zero());
+	assert_eq!(client.ret2(), (2, 2));
+	assert_eq!(client.ret6(), (6, 6, 6, 6, 6, 6));
+	assert_eq!(client.ret_var(), (6, vec![1, 2, 3, 5, 7, 11]));
+	assert_eq!(client.tuple_arg((6, vec![1, 2, 3])), 9);
+}
+
+fn ext_reset_truncating() {
+	// Like the host, copy only as much of the output as fits into the buffer
+	ext_reset(|e| e.endpoint(Address::zero(), Endpoint::new(Box::new(|_, input, result| {
+		let output = TupleReturnEndpoint::new(Instance).dispatch(input);
+		let len = ::std::cmp::min(output.len(), result.len());
+		result[..len].copy_from_slice(&output[..len]);
+		Ok(())
+	}))));
+}
+
+#[test]
+fn large_return_call() {
+	ext_reset_truncating();
+
+	let mut client = TupleReturnClient::new(Address::zero());
+	// With offset and length, 900 bytes still fit into the capacity
+	assert_eq!(client.try_ret_large(900), Ok(vec![7; 900]));
+	assert_eq!(client.try_ret_large(2000), Err(CallError::Truncated(1024)));
+
+	let mut client = TupleReturnClient::new(Address::zero()).return_capacity(4096);
+	assert_eq!(client.try_ret_large(2000), Ok(vec![7; 2000]));
+	assert_eq!(client.ret_large(2000), vec![7; 2000]);
+}
+
+#[test]
+fn large_return_call_ending_in_zeros() {
+	ext_reset_truncating();
+
+	// The truncated output ends in zero words all the same
+	let mut client = TupleReturnClient::new(Address::zero());
+	assert_eq!(client.try_ret_zeros(30), Ok(vec![0; 30]));
+	assert_eq!(client.try_ret_zeros(40), Err(CallError::Truncated(1024)));
+
+	let mut client = TupleReturnClient::new(Address::zero()).return_capacity(32);
+	assert_eq!(client.try_ret_zeros(0), Err(CallError::Truncated(32)));
+}
+
+#[test]
+#[should_panic(expected = "call output exceeds the return capacity of 1024 bytes")]
+fn large_return_call_truncated() {
+	ext_reset_truncating();
+
+	TupleReturnClient::new(Address::zero()).ret_large(2000);
+}
+
+#[test]
+fn malformed_return_call() {
+	// Boolean output other than zero or one
+	ext_reset(|e| e.endpoint(Address::zero(), Endpoint::new(Box::new(|_, _, result| {
+		result[31] = 2;
+		Ok(())
+	}))));
+
+	let mut client = TupleReturnClient::new(Address::zero());
+	match client.try_ret_flag() {
+		Err(CallError::Decode(error)) => assert_eq!(error.kind(), ErrorKind::InvalidBool),
+		other => panic!("unexpected result: {:?}", other),
+	}
+}
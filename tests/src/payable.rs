@@ -1,7 +1,7 @@
 #![allow(dead_code)]
 
 use pwasm_abi_derive::eth_abi;
-use pwasm_abi::eth::EndpointInterface;
+use pwasm_abi::eth::{DispatchError, EndpointInterface};
 
 use pwasm_test::{ext_reset};
 
@@ -52,6 +52,14 @@ fn non_payable_with_ret_value() {
 	NonPayableEndpoint::new(NonPayableContractInstance).dispatch(PAYLOAD_BOO);
 }
 
+#[test]
+fn non_payable_try_dispatch_value() {
+	ext_reset(|e| e.value(1.into()));
+	let mut endpoint = NonPayableEndpoint::new(NonPayableContractInstance);
+	assert_eq!(endpoint.try_dispatch_ctor(&[]), Err(DispatchError::ValueNotAccepted));
+	assert_eq!(endpoint.try_dispatch(PAYLOAD_BAZ), Err(DispatchError::ValueNotAccepted));
+}
+
 #[test]
 fn non_payable_constructor_no_value() {
 	NonPayableEndpoint::new(NonPayableContractInstance).dispatch_ctor(&[]);
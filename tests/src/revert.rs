@@ -0,0 +1,215 @@
+#![allow(dead_code)]
+
+use std::panic::{self, AssertUnwindSafe};
+
+use pwasm_abi::eth::{CallError, DispatchError, EndpointInterface, Revert};
+use pwasm_abi::types::{Address, String, U256};
+use pwasm_abi_derive::{eth_abi, AbiType};
+use pwasm_ethereum;
+use pwasm_test::{ext_reset, Endpoint};
+
+#[derive(AbiType, Clone, Debug, PartialEq)]
+pub struct InsufficientBalance {
+	available: U256,
+	required: U256,
+}
+
+#[eth_abi(RevertEndpoint, RevertClient)]
+pub trait RevertContract {
+	fn withdraw(&mut self, amount: U256) -> Result<U256, InsufficientBalance>;
+	fn check(&mut self, flag: bool) -> Result<(), String>;
+	fn check_static(&mut self, flag: bool) -> Result<(), &'static str>;
+}
+
+pub struct Instance {
+	balance: U256,
+}
+
+impl RevertContract for Instance {
+	fn withdraw(&mut self, amount: U256) -> Result<U256, InsufficientBalance> {
+		if amount > self.balance {
+			return Err(InsufficientBalance { available: self.balance, required: amount });
+		}
+		self.balance = self.balance - amount;
+		Ok(self.balance)
+	}
+
+	fn check(&mut self, flag: bool) -> Result<(), String> {
+		if flag { Ok(()) } else { Err("flag not set".into()) }
+	}
+
+	fn check_static(&mut self, flag: bool) -> Result<(), &'static str> {
+		if flag { Ok(()) } else { Err("static flag not set") }
+	}
+}
+
+// withdraw(uint256)
+fn withdraw_payload(amount: u8) -> Vec<u8> {
+	let mut payload = vec![0x2e, 0x1a, 0x7d, 0x4d];
+	payload.extend_from_slice(&[0u8; 31]);
+	payload.push(amount);
+	payload
+}
+
+// check(bool)
+fn check_payload(flag: bool) -> Vec<u8> {
+	let mut payload = vec![0x24, 0x1c, 0x59, 0x12];
+	payload.extend_from_slice(&[0u8; 31]);
+	payload.push(flag as u8);
+	payload
+}
+
+#[test]
+fn revert_ok() {
+	let mut endpoint = RevertEndpoint::new(Instance { balance: 100.into() });
+
+	let result = endpoint.try_dispatch(&withdraw_payload(10)).unwrap();
+	assert_eq!(U256::from(&result[..]), U256::from(90));
+
+	assert_eq!(endpoint.try_dispatch(&check_payload(true)), Ok(Vec::new()));
+}
+
+#[test]
+fn revert_custom_error() {
+	let mut endpoint = RevertEndpoint::new(Instance { balance: 100.into() });
+
+	// InsufficientBalance(uint256,uint256)
+	let mut expected = vec![0xcf, 0x47, 0x91, 0x81];
+	expected.extend_from_slice(&[0u8; 31]);
+	expected.push(100);
+	expected.extend_from_slice(&[0u8; 31]);
+	expected.push(200);
+
+	assert_eq!(
+		endpoint.try_dispatch(&withdraw_payload(200)),
+		Err(DispatchError::Revert(expected))
+	);
+	assert_eq!(endpoint.instance().balance, U256::from(100));
+}
+
+#[test]
+fn revert_reason() {
+	let mut endpoint = RevertEndpoint::new(Instance { balance: 100.into() });
+
+	// Error(string)
+	let mut expected = vec![0x08, 0xc3, 0x79, 0xa0];
+	expected.extend_from_slice(&[0u8; 31]);
+	expected.push(0x20);
+	expected.extend_from_slice(&[0u8; 31]);
+	expected.push(12);
+	expected.extend_from_slice(b"flag not set");
+	expected.extend_from_slice(&[0u8; 20]);
+
+	assert_eq!(
+		endpoint.try_dispatch(&check_payload(false)),
+		Err(DispatchError::Revert(expected))
+	);
+}
+
+#[test]
+fn dispatch_revert() {
+	let mut endpoint = RevertEndpoint::new(Instance { balance: 100.into() });
+
+	// InsufficientBalance(uint256,uint256)
+	let mut expected = vec![0xcf, 0x47, 0x91, 0x81];
+	expected.extend_from_slice(&[0u8; 31]);
+	expected.push(100);
+	expected.extend_from_slice(&[0u8; 31]);
+	expected.push(200);
+
+	let revert = panic::catch_unwind(AssertUnwindSafe(|| endpoint.dispatch(&withdraw_payload(200))))
+		.expect_err("dispatch should revert");
+	assert_eq!(revert.downcast_ref::<Revert>(), Some(&Revert(expected)));
+
+	// Error(string)
+	let mut expected = vec![0x08, 0xc3, 0x79, 0xa0];
+	expected.extend_from_slice(&[0u8; 31]);
+	expected.push(0x20);
+	expected.extend_from_slice(&[0u8; 31]);
+	expected.push(12);
+	expected.extend_from_slice(b"flag not set");
+	expected.extend_from_slice(&[0u8; 20]);
+
+	let revert = panic::catch_unwind(AssertUnwindSafe(|| endpoint.dispatch(&check_payload(false))))
+		.expect_err("dispatch should revert");
+	assert_eq!(revert.downcast_ref::<Revert>(), Some(&Revert(expected)));
+}
+
+fn ext_reset_with_revert() {
+	ext_reset(|e| e.endpoint(Address::zero(), Endpoint::new(Box::new(|_, input, result| {
+		let mut endpoint = RevertEndpoint::new(Instance { balance: 100.into() });
+		match endpoint.try_dispatch(input) {
+			Ok(output) => {
+				result[..output.len()].copy_from_slice(&output);
+				Ok(())
+			},
+			Err(err) => {
+				let payload = err.into_revert_payload();
+				result[..payload.len()].copy_from_slice(&payload);
+				Err(pwasm_ethereum::Error)
+			},
+		}
+	}))));
+}
+
+#[test]
+fn try_call_ok() {
+	ext_reset_with_revert();
+	let mut client = RevertClient::new(Address::zero());
+
+	assert_eq!(client.try_withdraw(10.into()), Ok(U256::from(90)));
+	assert_eq!(client.try_check(true), Ok(()));
+	assert_eq!(client.withdraw(10.into()), Ok(U256::from(90)));
+}
+
+#[test]
+fn try_call_custom_error() {
+	ext_reset_with_revert();
+	let mut client = RevertClient::new(Address::zero());
+
+	assert_eq!(
+		client.try_withdraw(200.into()),
+		Err(CallError::Custom(InsufficientBalance { available: 100.into(), required: 200.into() }))
+	);
+	assert_eq!(
+		client.withdraw(200.into()),
+		Err(InsufficientBalance { available: 100.into(), required: 200.into() })
+	);
+}
+
+#[test]
+fn try_call_reason() {
+	ext_reset_with_revert();
+	let mut client = RevertClient::new(Address::zero());
+
+	assert_eq!(client.try_check(false), Err(CallError::Reason("flag not set".into())));
+	assert_eq!(client.check(false), Err("flag not set".into()));
+	assert_eq!(client.check_static(false), Err("static flag not set"));
+}
+
+#[test]
+fn try_call_raw_revert() {
+	ext_reset(|e| e.endpoint(Address::zero(), Endpoint::new(Box::new(|_, _, result| {
+		result[..4].copy_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+		Err(pwasm_ethereum::Error)
+	}))));
+	let mut client = RevertClient::new(Address::zero()).return_capacity(4);
+
+	assert_eq!(client.try_check(true), Err(CallError::Revert(vec![0xde, 0xad, 0xbe, 0xef])));
+	// Methods returning a reason describe other failures as the reason
+	assert_eq!(client.check(true), Err("call reverted with 4 bytes of data".into()));
+	assert_eq!(client.check_static(true), Err("call reverted with 4 bytes of data"));
+}
+
+#[test]
+#[should_panic(expected = "call reverted with 4 bytes of data")]
+fn call_raw_revert() {
+	ext_reset(|e| e.endpoint(Address::zero(), Endpoint::new(Box::new(|_, _, result| {
+		result[..4].copy_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+		Err(pwasm_ethereum::Error)
+	}))));
+	let mut client = RevertClient::new(Address::zero());
+
+	// Methods returning a custom error panic on other failures
+	let _ = client.withdraw(10.into());
+}
@@ -0,0 +1,132 @@
+#![allow(dead_code)]
+
+use pwasm_abi::eth::{keccak, CallError, DispatchError, EndpointInterface, ErrorKind, Sink};
+use pwasm_abi::types::{Address, H256};
+use pwasm_abi_derive::eth_abi;
+use pwasm_test::{ext_get, ext_reset, Endpoint};
+
+#[eth_abi(StrictEndpoint, StrictClient, strict)]
+pub trait StrictContract {
+	fn constructor(&mut self, owner: Address);
+	fn reversed(&mut self, data: Vec<u8>) -> Vec<u8>;
+	#[event]
+	fn digested(&mut self, #[indexed] sender: Address, data: Vec<u8>);
+}
+
+#[eth_abi(LenientEndpoint)]
+pub trait LenientContract {
+	fn reversed(&mut self, data: Vec<u8>) -> Vec<u8>;
+}
+
+pub struct Instance;
+
+impl StrictContract for Instance {
+	fn constructor(&mut self, _owner: Address) {}
+	fn reversed(&mut self, mut data: Vec<u8>) -> Vec<u8> {
+		data.reverse();
+		data
+	}
+}
+
+impl LenientContract for Instance {
+	fn reversed(&mut self, mut data: Vec<u8>) -> Vec<u8> {
+		data.reverse();
+		data
+	}
+}
+
+fn reversed_payload(data: Vec<u8>) -> Vec<u8> {
+	let hash = keccak(b"reversed(bytes)");
+	let hash: &[u8] = hash.as_ref();
+	let selector = (hash[0] as u32) << 24 | (hash[1] as u32) << 16 | (hash[2] as u32) << 8 | hash[3] as u32;
+	let mut sink = Sink::with_selector(selector);
+	sink.push(data);
+	sink.finalize().unwrap()
+}
+
+#[test]
+fn strict_endpoint() {
+	let payload = reversed_payload(vec![1, 2, 3]);
+	let mut endpoint = StrictEndpoint::new(Instance);
+	assert_eq!(endpoint.try_dispatch(&payload), LenientEndpoint::new(Instance).try_dispatch(&payload));
+
+	// Trailing bytes are only rejected by the strict endpoint
+	let mut trailing = payload.clone();
+	trailing.extend_from_slice(&[0u8; 32]);
+	match endpoint.try_dispatch(&trailing) {
+		Err(DispatchError::Decode(error)) => assert_eq!(error.kind(), ErrorKind::TrailingBytes),
+		other => panic!("unexpected result {:?}", other),
+	}
+	assert_eq!(LenientEndpoint::new(Instance).try_dispatch(&trailing), endpoint.try_dispatch(&payload));
+
+	// Padding of the bytes following the 3 bytes of data has to be zero
+	let mut padded = payload.clone();
+	padded[4 + 64 + 31] = 0xff;
+	match endpoint.try_dispatch(&padded) {
+		Err(DispatchError::ArgumentDecode { index: 0, error }) => assert_eq!(error.kind(), ErrorKind::NonZeroPadding),
+		other => panic!("unexpected result {:?}", other),
+	}
+	assert!(LenientEndpoint::new(Instance).try_dispatch(&padded).is_ok());
+}
+
+#[test]
+fn strict_constructor() {
+	ext_reset(|e| e);
+	let mut endpoint = StrictEndpoint::new(Instance);
+	assert_eq!(endpoint.try_dispatch_ctor(&[0u8; 32]), Ok(()));
+	match endpoint.try_dispatch_ctor(&[0u8; 33]) {
+		Err(DispatchError::Decode(error)) => assert_eq!(error.kind(), ErrorKind::TrailingBytes),
+		other => panic!("unexpected result {:?}", other),
+	}
+}
+
+#[test]
+fn strict_event() {
+	ext_reset(|e| e);
+	let sender = Address::from([0x11; 20]);
+	assert_eq!(Instance.digested(sender, vec![1, 2, 3]), Ok(()));
+
+	let logs = ext_get().logs();
+	let (ref topics, ref data) = logs[0];
+	let event = strict_contract_events::Digested::decode_log(topics, data).expect("digested should be decoded");
+	assert_eq!((event.sender, event.data), (sender, vec![1, 2, 3]));
+
+	let mut trailing = data.clone();
+	trailing.extend_from_slice(&[0u8; 32]);
+	assert_eq!(
+		strict_contract_events::Digested::decode_log(topics, &trailing).map_err(|error| error.kind()).err(),
+		Some(ErrorKind::TrailingBytes)
+	);
+
+	// Padding of the address in the topic has to be zero
+	let mut topics = topics.clone();
+	let mut sender_topic = [0x11u8; 32];
+	sender_topic[..11].copy_from_slice(&[0u8; 11]);
+	topics[1] = H256::from(sender_topic);
+	assert_eq!(
+		strict_contract_events::Digested::decode_log(&topics, data).map_err(|error| error.kind()).err(),
+		Some(ErrorKind::NonZeroPadding)
+	);
+}
+
+#[test]
+fn strict_client() {
+	ext_reset(|e| e.endpoint(Address::zero(), Endpoint::new(Box::new(|_, input, result| {
+		let output = StrictEndpoint::new(Instance).dispatch(input);
+		result[..output.len()].copy_from_slice(&output);
+		// Padding of the output is not zero for an input of 5 bytes
+		if output.len() == 96 && output[63] == 5 {
+			result[95] = 0xff;
+		}
+		Ok(())
+	}))));
+
+	// Zeros filling the buffer of the client after the output are accepted
+	let mut client = StrictClient::new(Address::zero());
+	assert_eq!(client.reversed(vec![1, 2, 3]), vec![3, 2, 1]);
+
+	match client.try_reversed(vec![1, 2, 3, 4, 5]) {
+		Err(CallError::Decode(error)) => assert_eq!(error.kind(), ErrorKind::NonZeroPadding),
+		other => panic!("unexpected result {:?}", other),
+	}
+}
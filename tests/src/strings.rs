@@ -1,12 +1,16 @@
 #![allow(dead_code)]
 
-use pwasm_abi::eth::EndpointInterface;
+use pwasm_test::{ext_get, ext_reset};
+use pwasm_abi::eth::{AsLog, EndpointInterface};
 use pwasm_abi_derive::eth_abi;
 
 
 #[eth_abi(StringsEndpoint, StringsClient)]
 pub trait StringsContract {
 	fn string(&mut self, v: String);
+
+	#[event]
+	fn string_set(&mut self, indexed_v: String, length: u32);
 }
 
 const PAYLOAD_SAMPLE_1: &[u8] = &[
@@ -40,3 +44,32 @@ fn strings() {
 	let test_string = String::from("Ash nazg thrakatulûk agh burzum-ishi krimpatul");
 	assert_eq!(endpoint.inner.s1, test_string);
 }
+
+#[test]
+fn strings_indexed_event() {
+	#[derive(Default)]
+	pub struct Instance;
+
+	impl StringsContract for Instance {
+		fn string(&mut self, s: String) {
+			let length = s.len() as u32;
+			self.string_set(s, length).expect("string_set event encodes");
+		}
+	}
+
+	ext_reset(|e| e);
+	let mut endpoint = StringsEndpoint::new(Instance::default());
+	endpoint.dispatch(PAYLOAD_SAMPLE_1);
+
+	// Indexed strings are logged as the keccak hash of their content
+	let test_string = String::from("Ash nazg thrakatulûk agh burzum-ishi krimpatul");
+	let logs = ext_get().logs();
+	assert_eq!(logs.len(), 1);
+	let (ref topics, ref data) = logs[0];
+	assert_eq!(topics[1], test_string.as_log());
+	assert_eq!(topics[1], pwasm_abi::eth::keccak(test_string.as_bytes()));
+
+	let event = strings_contract_events::StringSet::decode_log(topics, data).expect("string_set should be decoded");
+	assert_eq!(event.indexed_v, test_string.as_log());
+	assert_eq!(event.length, 47);
+}
@@ -0,0 +1,138 @@
+#![allow(dead_code)]
+
+use pwasm_abi::eth::{keccak, AsLog, DispatchError, EndpointInterface, ErrorKind, PathSegment};
+use pwasm_abi::types::{Address, U256};
+use pwasm_abi_derive::{eth_abi, AbiType};
+use pwasm_test::{ext_get, ext_reset, Endpoint};
+
+#[derive(AbiType, Clone, Debug, PartialEq)]
+pub struct Position {
+	owner: Address,
+	amount: U256,
+}
+
+#[derive(AbiType, Clone, Debug, PartialEq)]
+pub struct Order {
+	id: u64,
+	position: Position,
+	note: String,
+}
+
+#[eth_abi(StructsEndpoint, StructsClient)]
+pub trait StructsContract {
+	fn open(&mut self, position: Position) -> U256;
+	fn place(&mut self, order: Order) -> Order;
+	fn position(&mut self, owner: Address) -> Position;
+
+	#[event]
+	fn placed(&mut self, #[indexed] order: Order, #[indexed] ids: [u64; 2], #[indexed] note: &[u8]);
+}
+
+pub struct Instance;
+
+impl StructsContract for Instance {
+	fn open(&mut self, position: Position) -> U256 {
+		position.amount
+	}
+
+	fn place(&mut self, mut order: Order) -> Order {
+		order.id += 1;
+		order
+	}
+
+	fn position(&mut self, owner: Address) -> Position {
+		Position { owner: owner, amount: 69.into() }
+	}
+}
+
+// open((address,uint256))
+const PAYLOAD_OPEN: &[u8] = &[
+	0xc0, 0xe9, 0x3e, 0x62,
+	0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,
+	0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x45,
+];
+
+// place((uint64,(address,uint256),string))
+const PAYLOAD_PLACE: &[u8] = &[
+	0xae, 0xda, 0xc3, 0x06,
+	0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20,
+	0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,
+	0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+	0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02,
+	0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80,
+	0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03,
+	0x61, 0x62, 0x63, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+#[test]
+fn struct_dispatch() {
+	let mut endpoint = StructsEndpoint::new(Instance);
+	let result = endpoint.dispatch(PAYLOAD_OPEN);
+
+	assert_eq!(&result[..], &PAYLOAD_OPEN[36..68]);
+}
+
+#[test]
+fn struct_decode_error_path() {
+	// Invalid UTF-8 in the note of the order
+	let mut payload = PAYLOAD_PLACE.to_vec();
+	payload[4 + 32 * 6] = 0xff;
+
+	let mut endpoint = StructsEndpoint::new(Instance);
+	match endpoint.try_dispatch(&payload) {
+		Err(DispatchError::ArgumentDecode { index: 0, error }) => {
+			assert_eq!(error.kind(), ErrorKind::InvalidUtf8);
+			assert_eq!(error.position(), Some(32 * 5));
+			assert_eq!(error.path(), &[PathSegment::Argument(0), PathSegment::Field("note")][..]);
+			assert_eq!(error.to_string(), "invalid utf-8 string decoding string at byte 160 in args[0].note");
+		},
+		other => panic!("Unexpected dispatch result {:?}", other),
+	}
+}
+
+#[test]
+fn struct_call() {
+	ext_reset(|e| e.endpoint(Address::zero(), Endpoint::new(Box::new(|_, input, result| {
+		let output = StructsEndpoint::new(Instance).dispatch(input);
+		result[..output.len()].copy_from_slice(&output);
+		Ok(())
+	}))));
+
+	let order = Order {
+		id: 1,
+		position: Position { owner: Address::zero(), amount: 2.into() },
+		note: "abc".into(),
+	};
+
+	let mut client = StructsClient::new(Address::zero());
+	assert_eq!(client.place(order.clone()), Order { id: 2, ..order });
+	assert_eq!(ext_get().calls()[0].input.as_ref(), PAYLOAD_PLACE);
+	assert_eq!(client.position(Address::zero()), Position { owner: Address::zero(), amount: 69.into() });
+}
+
+#[test]
+fn struct_indexed_event() {
+	ext_reset(|e| e);
+	let order = Order {
+		id: 1,
+		position: Position { owner: Address::zero(), amount: 2.into() },
+		note: "abc".into(),
+	};
+	assert_eq!(Instance.placed(order.clone(), [1, 2], b"abc"), Ok(()));
+
+	// Members of structs and elements of arrays are hashed padded to 32 bytes
+	let mut encoded_order = vec![0u8; 32 * 4];
+	encoded_order[31] = 1;
+	encoded_order[95] = 2;
+	encoded_order[96..99].copy_from_slice(b"abc");
+	let mut encoded_ids = vec![0u8; 32 * 2];
+	encoded_ids[31] = 1;
+	encoded_ids[63] = 2;
+
+	let logs = ext_get().logs();
+	let (ref topics, _) = logs[0];
+	assert_eq!(topics[1], keccak(&encoded_order));
+	assert_eq!(topics[1], order.as_log());
+	assert_eq!(topics[2], keccak(&encoded_ids));
+	assert_eq!(topics[3], keccak(b"abc"));
+}